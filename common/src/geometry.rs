@@ -0,0 +1,154 @@
+//! Geometry helpers
+
+/// 2D integer vector
+pub type Vector2D = euclid::default::Vector2D<i32>;
+
+/// Cardinal direction
+///
+/// Two offset conventions coexist in the puzzles:
+/// - `to_offset` uses a Y axis pointing up (north is `(0, 1)`),
+/// - `to_screen_offset` uses a Y axis pointing down (north is `(0, -1)`),
+///   which matches row-major screen coordinates.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Direction {
+    North,
+    East,
+    South,
+    West,
+}
+
+impl Direction {
+    /// List all directions, clockwise from north
+    pub fn all() -> [Self; 4] {
+        [Self::North, Self::East, Self::South, Self::West]
+    }
+
+    /// Get offset, with Y pointing up
+    pub fn to_offset(self) -> Vector2D {
+        match self {
+            Self::North => Vector2D::new(0, 1),
+            Self::South => Vector2D::new(0, -1),
+            Self::East => Vector2D::new(1, 0),
+            Self::West => Vector2D::new(-1, 0),
+        }
+    }
+
+    /// Get offset, with Y pointing down
+    pub fn to_screen_offset(self) -> Vector2D {
+        let offset = self.to_offset();
+        Vector2D::new(offset.x, -offset.y)
+    }
+
+    /// Turn left (counter-clockwise)
+    pub fn turn_left(self) -> Self {
+        match self {
+            Self::North => Self::West,
+            Self::West => Self::South,
+            Self::South => Self::East,
+            Self::East => Self::North,
+        }
+    }
+
+    /// Turn right (clockwise)
+    pub fn turn_right(self) -> Self {
+        match self {
+            Self::North => Self::East,
+            Self::East => Self::South,
+            Self::South => Self::West,
+            Self::West => Self::North,
+        }
+    }
+
+    /// Get opposite direction
+    pub fn opposite(self) -> Self {
+        match self {
+            Self::North => Self::South,
+            Self::East => Self::West,
+            Self::South => Self::North,
+            Self::West => Self::East,
+        }
+    }
+
+    /// From movement code (1: north, 2: south, 3: west, 4: east)
+    pub fn from_movement_code(code: i64) -> Self {
+        match code {
+            1 => Self::North,
+            2 => Self::South,
+            3 => Self::West,
+            4 => Self::East,
+            _ => panic!("unknown movement code: {}", code),
+        }
+    }
+
+    /// To movement code (1: north, 2: south, 3: west, 4: east)
+    pub fn to_movement_code(self) -> i64 {
+        match self {
+            Self::North => 1,
+            Self::South => 2,
+            Self::West => 3,
+            Self::East => 4,
+        }
+    }
+
+    /// Apply rotation code (0: turn left, 1: turn right)
+    pub fn with_rotation_code(self, code: i64) -> Self {
+        match code {
+            0 => self.turn_left(),
+            1 => self.turn_right(),
+            _ => panic!("unknown rotation code: {}", code),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_turn_left() {
+        assert_eq!(Direction::North.turn_left(), Direction::West);
+        assert_eq!(Direction::West.turn_left(), Direction::South);
+        assert_eq!(Direction::South.turn_left(), Direction::East);
+        assert_eq!(Direction::East.turn_left(), Direction::North);
+    }
+
+    #[test]
+    fn test_turn_right() {
+        assert_eq!(Direction::North.turn_right(), Direction::East);
+        assert_eq!(Direction::East.turn_right(), Direction::South);
+        assert_eq!(Direction::South.turn_right(), Direction::West);
+        assert_eq!(Direction::West.turn_right(), Direction::North);
+    }
+
+    #[test]
+    fn test_opposite() {
+        for dir in Direction::all().iter() {
+            assert_eq!(dir.opposite().opposite(), *dir);
+            assert_eq!(dir.turn_right().turn_right(), dir.opposite());
+            assert_eq!(
+                dir.to_offset() + dir.opposite().to_offset(),
+                Vector2D::zero()
+            );
+        }
+    }
+
+    #[test]
+    fn test_rotation_code() {
+        assert_eq!(Direction::North.with_rotation_code(0), Direction::West);
+        assert_eq!(Direction::North.with_rotation_code(1), Direction::East);
+    }
+
+    #[test]
+    fn test_offsets() {
+        assert_eq!(Direction::North.to_offset(), Vector2D::new(0, 1));
+        assert_eq!(Direction::North.to_screen_offset(), Vector2D::new(0, -1));
+        assert_eq!(Direction::East.to_screen_offset(), Vector2D::new(1, 0));
+    }
+
+    #[test]
+    fn test_movement_code() {
+        for dir in Direction::all().iter() {
+            assert_eq!(Direction::from_movement_code(dir.to_movement_code()), *dir);
+        }
+    }
+}
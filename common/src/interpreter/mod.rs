@@ -3,6 +3,12 @@
 mod opcode;
 mod parameter_mode;
 
+use std::io::{self, BufRead, Write};
+use std::time::Instant;
+
+/// Step count between two deadline checks
+const DEADLINE_POLL_STEPS: u64 = 4096;
+
 pub use opcode::{OpCode, Register};
 pub use parameter_mode::ParameterMode;
 
@@ -15,39 +21,211 @@ pub enum ExecutionState {
     Exit,
     /// Waiting
     Wait,
+    /// Failing
+    Error(InterpreterError),
+    /// Interrupted by a deadline
+    Timeout,
+}
+
+/// Interpreter error
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InterpreterError {
+    /// Arithmetic overflow
+    Overflow,
+    /// Write to an immediate mode parameter, in strict mode
+    ImmediateWrite,
+}
+
+/// Radix used when dumping values
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Radix {
+    /// Decimal
+    Dec,
+    /// Signed hexadecimal, without prefix
+    Hex,
+}
+
+impl Radix {
+    /// Format value
+    pub fn format(self, value: i64) -> String {
+        match self {
+            Self::Dec => value.to_string(),
+            Self::Hex if value < 0 => format!("-{:x}", value.unsigned_abs()),
+            Self::Hex => format!("{:x}", value),
+        }
+    }
+}
+
+/// State changed by an executed step, used to step back
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "json", derive(serde::Serialize, serde::Deserialize))]
+struct HistoryEntry {
+    cursor: usize,
+    relative_base: i64,
+    /// Written memory cell position and previous value
+    memory: Option<(usize, i64)>,
+    /// Memory length before the step
+    memory_len: usize,
+    /// Consumed input
+    input: Option<i64>,
+    /// Produced output position, counting drained values
+    output: Option<usize>,
 }
 
 /// Interpreter
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "json", derive(serde::Serialize, serde::Deserialize))]
 pub struct Interpreter {
     data: Vec<i64>,
     initial: Vec<i64>,
     cursor: usize,
     input_stream: Vec<i64>,
     output_stream: Vec<i64>,
-    debug: bool,
+    /// Count of output values removed from the stream front
+    output_drained: usize,
     relative_base: i64,
+    checked_arithmetic: bool,
+    strict: bool,
+    empty_input_default: Option<i64>,
+    history: Option<Vec<HistoryEntry>>,
+    uninitialized_value: i64,
+    steps_executed: u64,
+    halted: bool,
 }
 
 impl Interpreter {
-    /// Create intepreter from input text
+    /// Create intepreter from input text.
+    /// Values can span multiple lines, and lines starting with `#` are ignored
     pub fn new(input_txt: &str) -> Self {
-        let data: Vec<i64> = input_txt.split(',').map(|x| x.parse().unwrap()).collect();
+        let data = Self::parse_code(input_txt).unwrap_or_else(|e| panic!("{}", e));
 
         Self {
             initial: data.clone(),
             data,
             cursor: 0,
             output_stream: vec![],
+            output_drained: 0,
             input_stream: vec![],
-            debug: false,
             relative_base: 0,
+            checked_arithmetic: false,
+            strict: false,
+            empty_input_default: None,
+            history: None,
+            uninitialized_value: 0,
+            steps_executed: 0,
+            halted: false,
+        }
+    }
+
+    /// Parse program values.
+    /// Values can span multiple lines, and lines starting with `#` are ignored
+    fn parse_code(input_txt: &str) -> Result<Vec<i64>, String> {
+        input_txt
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.starts_with('#'))
+            .flat_map(|line| line.split(','))
+            .map(str::trim)
+            .filter(|x| !x.is_empty())
+            .map(|x| x.parse().map_err(|_| format!("invalid value: {}", x)))
+            .collect()
+    }
+
+    /// Check that a program parses, and decode instructions linearly
+    /// until an Exit instruction, without running it
+    pub fn validate_program(code: &str) -> Result<(), String> {
+        let data = Self::parse_code(code)?;
+        let mut cursor = 0;
+
+        while cursor < data.len() {
+            match OpCode::try_parse(&data[cursor..]) {
+                Ok((OpCode::Exit, _)) => return Ok(()),
+                Ok((_, len)) => cursor += len,
+                Err(e) => return Err(format!("invalid instruction at {}: {}", cursor, e)),
+            }
         }
+
+        Err("no exit instruction found".to_owned())
     }
 
-    /// Set debug mode
-    pub fn set_debug_mode(&mut self, value: bool) {
-        self.debug = value;
+    /// Serialize interpreter state to JSON
+    #[cfg(feature = "json")]
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("interpreter state should be serializable")
+    }
+
+    /// Deserialize interpreter state from JSON
+    #[cfg(feature = "json")]
+    pub fn from_json(json: &str) -> Result<Self, serde_json::Error> {
+        serde_json::from_str(json)
+    }
+
+    /// Set checked arithmetic mode.
+    /// When enabled, an overflowing addition or multiplication
+    /// stops the execution with an error
+    pub fn set_checked_arithmetic(&mut self, value: bool) {
+        self.checked_arithmetic = value;
+    }
+
+    /// Set strict mode.
+    /// When enabled, writing to an immediate mode parameter
+    /// stops the execution with an error
+    pub fn set_strict(&mut self, value: bool) {
+        self.strict = value;
+    }
+
+    /// Set value of memory beyond the program, 0 by default
+    pub fn set_uninitialized_value(&mut self, value: i64) {
+        self.uninitialized_value = value;
+    }
+
+    /// Enable history recording.
+    /// Each executed step is recorded, so it can be undone with `step_back`
+    pub fn enable_history(&mut self) {
+        if self.history.is_none() {
+            self.history = Some(vec![]);
+        }
+    }
+
+    /// Undo last executed step, if history is enabled.
+    /// Return false if there is no step to undo
+    pub fn step_back(&mut self) -> bool {
+        let entry = match self.history.as_mut().and_then(Vec::pop) {
+            Some(entry) => entry,
+            None => return false,
+        };
+
+        self.cursor = entry.cursor;
+        self.relative_base = entry.relative_base;
+        if let Some((position, value)) = entry.memory {
+            if position < entry.memory_len {
+                self.set_value(position, value);
+            }
+        }
+        // Memory allocated by the step is released
+        self.data.truncate(entry.memory_len);
+        self.halted = false;
+        if let Some(input) = entry.input {
+            self.input_stream.insert(0, input);
+        }
+        // Output already drained by the caller is left alone
+        if let Some(index) = entry
+            .output
+            .and_then(|position| position.checked_sub(self.output_drained))
+        {
+            if index < self.output_stream.len() {
+                self.output_stream.remove(index);
+            }
+        }
+        self.steps_executed -= 1;
+
+        true
+    }
+
+    /// Set value read on an empty input stream.
+    /// When unset, the interpreter waits for input
+    pub fn set_empty_input_default(&mut self, value: Option<i64>) {
+        self.empty_input_default = value;
     }
 
     /// Push input value
@@ -55,6 +233,20 @@ impl Interpreter {
         self.input_stream.push(input);
     }
 
+    /// Push comma-separated input values, in order.
+    /// Nothing is pushed if a value is not a number
+    pub fn push_input_str(&mut self, input: &str) -> Result<(), String> {
+        let values = input
+            .split(',')
+            .map(str::trim)
+            .filter(|x| !x.is_empty())
+            .map(|x| x.parse().map_err(|_| format!("invalid input value: {}", x)))
+            .collect::<Result<Vec<i64>, _>>()?;
+
+        self.input_stream.extend(values);
+        Ok(())
+    }
+
     /// Pop input
     pub fn pop_input(&mut self) -> Option<i64> {
         if self.input_stream.is_empty() {
@@ -64,6 +256,16 @@ impl Interpreter {
         }
     }
 
+    /// Get input queue length
+    pub fn input_len(&self) -> usize {
+        self.input_stream.len()
+    }
+
+    /// Get next input without consuming it
+    pub fn peek_input(&self) -> Option<i64> {
+        self.input_stream.first().copied()
+    }
+
     /// Push output value
     pub fn push_output(&mut self, value: i64) {
         self.output_stream.push(value);
@@ -71,6 +273,7 @@ impl Interpreter {
 
     /// Set output values
     pub fn set_output_values(&mut self, values: Vec<i64>) {
+        self.output_drained += self.output_stream.len();
         self.output_stream = values;
     }
 
@@ -79,14 +282,20 @@ impl Interpreter {
         if self.output_stream.is_empty() {
             None
         } else {
+            self.output_drained += 1;
             Some(self.output_stream.remove(0))
         }
     }
 
+    /// Drain output stream into another interpreter input stream
+    pub fn pipe_output_into(&mut self, other: &mut Interpreter) {
+        self.output_drained += self.output_stream.len();
+        other.input_stream.append(&mut self.output_stream);
+    }
+
     /// Run and dump
     pub fn run_and_dump(input_txt: &str) -> String {
         let mut interpreter = Self::new(input_txt);
-        interpreter.set_debug_mode(true);
         interpreter.run();
         interpreter.dump()
     }
@@ -101,6 +310,11 @@ impl Interpreter {
 
     /// Run with input/output
     pub fn run_with_input_output(input_txt: &str, input: &[i64]) -> String {
+        Self::run_capturing(input_txt, input).1
+    }
+
+    /// Run with input, then dump memory and output
+    pub fn run_capturing(input_txt: &str, input: &[i64]) -> (String, String) {
         let mut interpreter = Self::new(input_txt);
         for i in input {
             interpreter.push_input(*i);
@@ -108,7 +322,7 @@ impl Interpreter {
 
         interpreter.run();
 
-        interpreter.dump_output()
+        (interpreter.dump(), interpreter.dump_output())
     }
 
     /// Allocate memory
@@ -121,21 +335,17 @@ impl Interpreter {
             );
         }
 
-        if self.debug {
-            println!("Allocating memory from {} to {} ...", data_len, up_to);
-        }
-
         for _ in data_len..=up_to {
-            self.data.push(0);
+            self.data.push(self.uninitialized_value);
         }
     }
 
     /// Get value at position
     pub fn get_value(&self, position: usize) -> i64 {
         // If position does not exist in memory,
-        // return default memory: 0
+        // return default memory
         if position >= self.data.len() {
-            0
+            self.uninitialized_value
         } else {
             self.data[position]
         }
@@ -197,26 +407,54 @@ impl Interpreter {
     /// Set cursor value
     pub fn set_cursor_value(&mut self, value: usize) {
         self.cursor = value;
+        self.halted = false;
     }
 
     /// Restore data
     pub fn reset_intepreter(&mut self) {
-        self.data = self.initial.clone();
+        // Reuse the memory allocation
+        self.data.clone_from(&self.initial);
         self.cursor = 0;
         self.input_stream.clear();
         self.output_stream.clear();
+        self.output_drained = 0;
         self.relative_base = 0;
+        self.steps_executed = 0;
+        self.halted = false;
+        if let Some(history) = &mut self.history {
+            history.clear();
+        }
+    }
+
+    /// Get executed instruction count.
+    /// Instructions waiting for input or failing are not counted
+    pub fn steps_executed(&self) -> u64 {
+        self.steps_executed
     }
 
     /// Get stream at cursor
     pub fn get_stream_at_cursor(&self) -> &[i64] {
-        &self.data[self.cursor as usize..]
+        &self.data[self.cursor..]
+    }
+
+    /// Parse opcode at cursor without executing it.
+    /// Return `None` when the cursor does not point to a valid instruction
+    pub fn peek_opcode(&self) -> Option<OpCode> {
+        let code_stream = self.data.get(self.cursor..).unwrap_or(&[]);
+        OpCode::try_parse(code_stream)
+            .ok()
+            .map(|(opcode, _)| opcode)
     }
 
     /// Dump intepreter data
     pub fn dump(&self) -> String {
-        let str_dump: Vec<String> = self.data.iter().map(|x| x.to_string()).collect();
-        str_dump.join(",")
+        self.dump_with(",", Radix::Dec)
+    }
+
+    /// Dump intepreter data with a specific separator and radix
+    pub fn dump_with(&self, sep: &str, radix: Radix) -> String {
+        let str_dump: Vec<String> = self.data.iter().map(|x| radix.format(*x)).collect();
+        str_dump.join(sep)
     }
 
     /// Dump output
@@ -227,9 +465,15 @@ impl Interpreter {
 
     // Clear output
     pub fn clear_output(&mut self) {
+        self.output_drained += self.output_stream.len();
         self.output_stream.clear();
     }
 
+    /// Clear queued input, keeping memory and cursor
+    pub fn clear_input(&mut self) {
+        self.input_stream.clear();
+    }
+
     /// Get input stream
     pub fn get_input_stream(&self) -> &[i64] {
         &self.input_stream
@@ -242,18 +486,62 @@ impl Interpreter {
 
     /// Run step
     pub fn step(&mut self) -> (OpCode, ExecutionState) {
-        let code_stream = self.get_stream_at_cursor();
-        if code_stream.is_empty() {
-            return (OpCode::Exit, ExecutionState::Exit);
+        if self.history.is_none() {
+            return self.execute_step();
+        }
+
+        let steps_executed = self.steps_executed;
+        let mut entry = HistoryEntry {
+            cursor: self.cursor,
+            relative_base: self.relative_base,
+            memory: None,
+            memory_len: self.data.len(),
+            input: self.peek_input(),
+            output: None,
+        };
+        if let Some(reg) = self.peek_opcode().and_then(|op| op.write_register()) {
+            let position = self.read_output_register(reg) as usize;
+            entry.memory = Some((position, self.get_value(position)));
         }
+        let input_len = self.input_len();
+        let output_len = self.output_stream.len();
 
-        if self.debug {
-            println!("Reading stream {:?}", code_stream);
+        let result = self.execute_step();
+        if self.steps_executed > steps_executed {
+            if self.input_len() == input_len {
+                entry.input = None;
+            }
+            if self.output_stream.len() > output_len {
+                entry.output = Some(self.output_drained + output_len);
+            }
+            if let Some(history) = &mut self.history {
+                history.push(entry);
+            }
+        }
+
+        result
+    }
+
+    fn execute_step(&mut self) -> (OpCode, ExecutionState) {
+        let code_stream = self.get_stream_at_cursor();
+        // Exit is only executed once
+        if self.halted || code_stream.is_empty() {
+            return (OpCode::Exit, ExecutionState::Exit);
         }
 
         let (opcode, count) = OpCode::parse(code_stream);
-        if self.debug {
-            println!("Opcode: {:?}", opcode.dump());
+
+        if self.strict {
+            if let Some(Register {
+                mode: ParameterMode::Immediate,
+                ..
+            }) = opcode.write_register()
+            {
+                return (
+                    opcode,
+                    ExecutionState::Error(InterpreterError::ImmediateWrite),
+                );
+            }
         }
 
         match opcode {
@@ -261,37 +549,48 @@ impl Interpreter {
                 let v1 = self.read_register(r1);
                 let v2 = self.read_register(r2);
                 let v3 = self.read_output_register(r3);
-                self.set_value(v3 as usize, v1 + v2);
-                self.advance_cursor(count);
+                let value = if self.checked_arithmetic {
+                    v1.checked_add(v2)
+                } else {
+                    Some(v1 + v2)
+                };
+
+                if let Some(value) = value {
+                    self.set_value(v3 as usize, value);
+                    self.advance_cursor(count);
+                } else {
+                    return (opcode, ExecutionState::Error(InterpreterError::Overflow));
+                }
             }
             OpCode::Multiply(r1, r2, r3) => {
                 let v1 = self.read_register(r1);
                 let v2 = self.read_register(r2);
                 let v3 = self.read_output_register(r3);
-                self.set_value(v3 as usize, v1 * v2);
-                self.advance_cursor(count);
+                let value = if self.checked_arithmetic {
+                    v1.checked_mul(v2)
+                } else {
+                    Some(v1 * v2)
+                };
+
+                if let Some(value) = value {
+                    self.set_value(v3 as usize, value);
+                    self.advance_cursor(count);
+                } else {
+                    return (opcode, ExecutionState::Error(InterpreterError::Overflow));
+                }
             }
             OpCode::Store(r) => {
-                if let Some(input) = self.pop_input() {
-                    if self.debug {
-                        println!("Getting input {}", input);
-                    }
+                if let Some(input) = self.pop_input().or(self.empty_input_default) {
                     let output = self.read_output_register(r);
                     self.set_value(output as usize, input);
                     self.advance_cursor(count);
                 } else {
-                    if self.debug {
-                        println!("[WAITING]");
-                    }
                     return (opcode, ExecutionState::Wait);
                 }
             }
             OpCode::Show(r) => {
                 let v = self.read_register(r);
                 self.push_output(v);
-                if self.debug {
-                    println!("Outputting: {}", v);
-                }
                 self.advance_cursor(count);
             }
             OpCode::JumpIfTrue(ri, ro) => {
@@ -340,19 +639,19 @@ impl Interpreter {
                 self.advance_cursor(count);
             }
             OpCode::Exit => {
+                self.halted = true;
+                self.steps_executed += 1;
                 return (opcode, ExecutionState::Exit);
             }
         }
 
+        self.steps_executed += 1;
         (opcode, ExecutionState::Next)
     }
 
     /// Run interpreter on initial data
     pub fn run(&mut self) -> String {
         let mut output = String::new();
-        if self.debug {
-            println!("Interpreter input: {:?}", self.get_input_stream());
-        }
 
         loop {
             let (opcode, state) = self.step();
@@ -363,14 +662,145 @@ impl Interpreter {
                 ExecutionState::Next => (),
                 ExecutionState::Exit => break,
                 ExecutionState::Wait => break,
+                ExecutionState::Error(_) => break,
+                ExecutionState::Timeout => break,
             }
         }
 
-        if self.debug {
-            println!("Interpreter output: {:?}", self.get_output_stream());
-        }
         output
     }
+
+    /// Run interpreter until it exits or waits for input.
+    /// Return the terminal execution state
+    pub fn run_until_halt(&mut self) -> ExecutionState {
+        loop {
+            let (_, state) = self.step();
+            if state != ExecutionState::Next {
+                return state;
+            }
+        }
+    }
+
+    /// Run interpreter until it exits, waits for input, or reaches the deadline.
+    /// The clock is only checked every few thousand steps.
+    /// On timeout, the state is preserved so the execution can be resumed
+    pub fn run_with_deadline(&mut self, deadline: Instant) -> ExecutionState {
+        let mut steps_before_poll = DEADLINE_POLL_STEPS;
+
+        loop {
+            let (_, state) = self.step();
+            if state != ExecutionState::Next {
+                return state;
+            }
+
+            steps_before_poll -= 1;
+            if steps_before_poll == 0 {
+                if Instant::now() >= deadline {
+                    return ExecutionState::Timeout;
+                }
+
+                steps_before_poll = DEADLINE_POLL_STEPS;
+            }
+        }
+    }
+
+    /// Run interpreter until it outputs a value, exits or waits for input.
+    /// Return the values output during this call (left in the output stream)
+    /// and the last execution state
+    pub fn run_to_output(&mut self) -> (Vec<i64>, ExecutionState) {
+        let output_len = self.output_stream.len();
+
+        loop {
+            let (_, state) = self.step();
+            if state != ExecutionState::Next || self.output_stream.len() > output_len {
+                return (self.output_stream[output_len..].to_vec(), state);
+            }
+        }
+    }
+
+    /// Run interpreter and check if the output stream equals the initial program
+    pub fn outputs_self(&mut self) -> bool {
+        self.run();
+        self.output_stream == self.initial
+    }
+
+    /// Run ASCII program on the console: stdin lines are used as input,
+    /// and output is printed as characters
+    pub fn run_interactive(&mut self) -> io::Result<ExecutionState> {
+        let stdin = io::stdin();
+        let mut input = stdin.lock();
+        let mut output = io::stdout();
+        self.run_interactive_with(&mut input, &mut output)
+    }
+
+    /// Run ASCII program, reading input lines from `input` when waiting
+    /// and writing output characters to `output`.
+    /// Non-ASCII output values are written as numbers.
+    /// Return `Wait` when `input` is exhausted
+    pub fn run_interactive_with(
+        &mut self,
+        input: &mut dyn BufRead,
+        output: &mut dyn Write,
+    ) -> io::Result<ExecutionState> {
+        loop {
+            let (_, state) = self.step();
+            while let Some(value) = self.pop_output() {
+                match value {
+                    0..=127 => write!(output, "{}", value as u8 as char)?,
+                    _ => writeln!(output, "{}", value)?,
+                }
+            }
+
+            match state {
+                ExecutionState::Next => (),
+                ExecutionState::Wait => {
+                    // Show prompt before blocking
+                    output.flush()?;
+
+                    let mut line = String::new();
+                    if input.read_line(&mut line)? == 0 {
+                        return Ok(state);
+                    }
+
+                    for c in line.trim_end_matches(&['\r', '\n'][..]).bytes() {
+                        self.push_input(c.into());
+                    }
+                    self.push_input(10);
+                }
+                _ => {
+                    output.flush()?;
+                    return Ok(state);
+                }
+            }
+        }
+    }
+
+    /// Run interpreter, writing one trace line per step to `out`.
+    /// Each line is formatted as `address | opcode | relative_base`,
+    /// the relative base being read after the step is executed.
+    pub fn run_traced(&mut self, out: &mut dyn Write) -> io::Result<()> {
+        loop {
+            let address = self.cursor;
+            let (opcode, state) = self.step();
+            writeln!(
+                out,
+                "{} | {} | {}",
+                address,
+                opcode.dump(),
+                self.relative_base
+            )?;
+
+            match state {
+                ExecutionState::Next => (),
+                ExecutionState::Exit => break,
+                ExecutionState::Wait => break,
+                ExecutionState::Error(_) => break,
+                ExecutionState::Timeout => break,
+            }
+        }
+
+        Ok(())
+    }
 }
 
 #[cfg(test)]
@@ -425,67 +855,494 @@ mod tests {
         assert_eq!(interpreter.run(), trace.to_owned());
     }
 
+    #[test]
+    fn test_run_traced() {
+        let code = "3,3,1105,-1,9,1101,0,0,12,4,12,99,1";
+        let trace = "0 | STORE 3 | 0\n\
+                     2 | JMPT [8], [9] | 0\n\
+                     9 | SHOW 12 | 0\n\
+                     11 | EXIT | 0\n";
+
+        let mut interpreter = Interpreter::new(code);
+        interpreter.push_input(8);
+
+        let mut buffer: Vec<u8> = vec![];
+        interpreter.run_traced(&mut buffer).unwrap();
+        assert_eq!(String::from_utf8(buffer).unwrap(), trace);
+        assert_eq!(interpreter.dump_output(), "1".to_owned());
+    }
+
+    #[test]
+    fn test_run_until_halt() {
+        let mut interpreter = Interpreter::new("3,0,4,0,99");
+        assert_eq!(interpreter.run_until_halt(), ExecutionState::Wait);
+        interpreter.push_input(5);
+        assert_eq!(interpreter.run_until_halt(), ExecutionState::Exit);
+        assert_eq!(interpreter.dump_output(), "5".to_owned());
+
+        let mut interpreter = Interpreter::new("99");
+        assert_eq!(interpreter.run_until_halt(), ExecutionState::Exit);
+    }
+
+    #[test]
+    fn test_annotated_program() {
+        let code = "# Output input + 10
+                    3, 9,
+                    1001, 9, 10, 9
+                    # Show result
+                    4, 9,
+                    99,
+                    0
+                    ";
+        let mut interp = Interpreter::new(code);
+        assert_eq!(interp.dump(), "3,9,1001,9,10,9,4,9,99,0".to_owned());
+
+        interp.push_input(5);
+        interp.run();
+        assert_eq!(interp.dump_output(), "15".to_owned());
+    }
+
+    #[test]
+    fn test_peek_opcode() {
+        let mut interp = Interpreter::new("1002,4,3,4,33");
+        assert_eq!(
+            interp.peek_opcode(),
+            Some(OpCode::Multiply(
+                Register::new(4, ParameterMode::Position),
+                Register::new(3, ParameterMode::Immediate),
+                Register::new(4, ParameterMode::Position)
+            ))
+        );
+        assert_eq!(interp.peek_opcode().unwrap().dump(), "MUL 4, [3], 4");
+        assert_eq!(interp.get_stream_at_cursor().len(), 5);
+
+        interp.step();
+        assert_eq!(interp.peek_opcode(), Some(OpCode::Exit));
+
+        interp.set_cursor_value(5);
+        assert_eq!(interp.peek_opcode(), None);
+
+        // Data cell and truncated instruction
+        let mut interp = Interpreter::new("1,0,0,0,99,42,1,0");
+        interp.set_cursor_value(5);
+        assert_eq!(interp.peek_opcode(), None);
+        interp.set_cursor_value(6);
+        assert_eq!(interp.peek_opcode(), None);
+    }
+
+    #[test]
+    fn test_step_back() {
+        let code = "3,11,1002,11,3,11,4,11,99,0,0,0";
+        let mut interp = Interpreter::new(code);
+        assert!(!interp.step_back());
+
+        interp.enable_history();
+        interp.push_input(5);
+        interp.step();
+        let one_step = interp.clone();
+        interp.step();
+        interp.step();
+        assert_eq!(interp.get_output_stream(), &[15]);
+
+        assert!(interp.step_back());
+        assert!(interp.step_back());
+        assert_eq!(interp.dump(), one_step.dump());
+        assert_eq!(interp.steps_executed(), 1);
+        assert_eq!(interp.get_output_stream(), &[] as &[i64]);
+
+        // Input is restored
+        assert!(interp.step_back());
+        assert_eq!(interp.dump(), code.to_owned());
+        assert_eq!(interp.peek_input(), Some(5));
+        assert!(!interp.step_back());
+
+        interp.run();
+        assert_eq!(interp.get_output_stream(), &[15]);
+
+        // Relative base
+        let mut interp = Interpreter::new("109,5,109,-2,99");
+        interp.enable_history();
+        interp.run();
+        assert!(interp.step_back());
+        assert!(interp.step_back());
+        assert_eq!(interp.peek_opcode().unwrap().dump(), "ARB [-2]");
+        assert_eq!(interp.relative_base, 5);
+        interp.step();
+        assert_eq!(interp.peek_opcode(), Some(OpCode::Exit));
+
+        // Memory grown by an out-of-range write shrinks back
+        let mut interp = Interpreter::new("1101,1,2,20,99");
+        interp.enable_history();
+        interp.step();
+        assert_eq!(interp.get_value(20), 3);
+        assert!(interp.step_back());
+        assert_eq!(interp.dump(), "1101,1,2,20,99".to_owned());
+
+        // Drained output is not popped again
+        let mut interp = Interpreter::new("104,1,104,2,99");
+        interp.enable_history();
+        interp.step();
+        interp.step();
+        assert_eq!(interp.pop_output(), Some(1));
+        assert!(interp.step_back());
+        assert!(interp.get_output_stream().is_empty());
+        interp.push_output(5);
+        assert!(interp.step_back());
+        assert_eq!(interp.get_output_stream(), &[5]);
+
+        // Only the value emitted by the step is removed
+        let mut interp = Interpreter::new("104,5,99");
+        interp.enable_history();
+        interp.push_output(5);
+        interp.step();
+        assert_eq!(interp.pop_output(), Some(5));
+        interp.push_output(7);
+        assert!(interp.step_back());
+        assert_eq!(interp.get_output_stream(), &[7]);
+
+        let mut interp = Interpreter::new("104,5,99");
+        interp.enable_history();
+        interp.push_output(5);
+        interp.step();
+        interp.clear_output();
+        assert!(interp.step_back());
+        assert!(interp.get_output_stream().is_empty());
+    }
+
+    #[test]
+    fn test_run_capturing() {
+        assert_eq!(
+            Interpreter::run_capturing("1002,4,3,4,33", &[]),
+            ("1002,4,3,4,99".to_owned(), "".to_owned())
+        );
+        assert_eq!(
+            Interpreter::run_capturing("3,0,4,0,99", &[42]),
+            ("42,0,4,0,99".to_owned(), "42".to_owned())
+        );
+    }
+
+    #[test]
+    fn test_uninitialized_value() {
+        let mut interp = Interpreter::new("4,1000,99");
+        interp.run();
+        assert_eq!(interp.get_output_stream(), &[0]);
+
+        let mut interp = Interpreter::new("4,1000,1101,1,1,5,99");
+        interp.set_uninitialized_value(-999);
+        assert_eq!(interp.get_value(1_000_000), -999);
+        interp.run();
+        assert_eq!(interp.get_output_stream(), &[-999]);
+
+        // Allocated memory is filled too
+        interp.set_value(10, 1);
+        assert_eq!(interp.get_value(9), -999);
+        assert_eq!(interp.get_value(10), 1);
+    }
+
+    #[test]
+    fn test_peek_input() {
+        let mut interp = Interpreter::new("3,0,99");
+        assert_eq!(interp.input_len(), 0);
+        assert_eq!(interp.peek_input(), None);
+
+        interp.push_input(5);
+        interp.push_input(6);
+        assert_eq!(interp.input_len(), 2);
+        assert_eq!(interp.peek_input(), Some(5));
+
+        interp.run();
+        assert_eq!(interp.input_len(), 1);
+        assert_eq!(interp.peek_input(), Some(6));
+    }
+
+    #[test]
+    fn test_push_input_str() {
+        let mut interp = Interpreter::new("99");
+        assert_eq!(interp.push_input_str("4,3, 2,1,0\n"), Ok(()));
+        assert_eq!(interp.input_len(), 5);
+        for &expected in &[4, 3, 2, 1, 0] {
+            assert_eq!(interp.pop_input(), Some(expected));
+        }
+
+        assert_eq!(
+            interp.push_input_str("1,a,3"),
+            Err("invalid input value: a".to_owned())
+        );
+        assert_eq!(interp.input_len(), 0);
+    }
+
+    #[test]
+    fn test_validate_program() {
+        assert_eq!(Interpreter::validate_program("1,0,0,0,99"), Ok(()));
+        assert_eq!(
+            Interpreter::validate_program("1,0,0,0"),
+            Err("no exit instruction found".to_owned())
+        );
+        assert_eq!(
+            Interpreter::validate_program("1,0,0"),
+            Err("invalid instruction at 0: missing arguments: 3 < 4".to_owned())
+        );
+        assert_eq!(
+            Interpreter::validate_program("1,0,0,0,42,99"),
+            Err("invalid instruction at 4: unsupported opcode: 42".to_owned())
+        );
+        assert_eq!(
+            Interpreter::validate_program("1,0,a"),
+            Err("invalid value: a".to_owned())
+        );
+        // Data after Exit is not decoded
+        assert_eq!(Interpreter::validate_program("1002,4,3,4,99,42"), Ok(()));
+    }
+
+    #[test]
+    fn test_clear_input() {
+        let mut interp = Interpreter::new("3,0,99");
+        interp.push_input(5);
+        interp.push_input(6);
+        interp.step();
+        assert_eq!(interp.get_value(0), 5);
+
+        interp.clear_input();
+        assert_eq!(interp.input_len(), 0);
+        assert_eq!(interp.get_value(0), 5);
+        assert_eq!(interp.get_value(2), 99);
+    }
+
+    #[test]
+    fn test_pipe_output_into() {
+        let mut source = Interpreter::new("104,7,104,8,99");
+        source.run();
+
+        let mut target = Interpreter::new("3,0,3,1,4,1,4,0,99");
+        source.pipe_output_into(&mut target);
+        assert!(source.get_output_stream().is_empty());
+        assert_eq!(target.get_input_stream(), &[7, 8]);
+
+        target.run();
+        assert_eq!(target.dump_output(), "8,7".to_owned());
+    }
+
+    #[test]
+    fn test_checked_arithmetic() {
+        let code = "1102,4611686018427387904,4,5,99,0";
+        let mut interpreter = Interpreter::new(code);
+        interpreter.set_checked_arithmetic(true);
+        assert_eq!(
+            interpreter.run_until_halt(),
+            ExecutionState::Error(InterpreterError::Overflow)
+        );
+        // Memory is untouched
+        assert_eq!(interpreter.dump(), code.to_owned());
+
+        let mut interpreter = Interpreter::new("1101,1,2,5,99,0");
+        interpreter.set_checked_arithmetic(true);
+        assert_eq!(interpreter.run_until_halt(), ExecutionState::Exit);
+        assert_eq!(interpreter.get_value(5), 3);
+    }
+
+    #[test]
+    fn test_strict() {
+        let code = "11101,1,2,5,99,0";
+        let mut interpreter = Interpreter::new(code);
+        assert_eq!(interpreter.run_until_halt(), ExecutionState::Exit);
+        assert_eq!(interpreter.get_value(5), 3);
+
+        let mut interpreter = Interpreter::new(code);
+        interpreter.set_strict(true);
+        assert_eq!(
+            interpreter.run_until_halt(),
+            ExecutionState::Error(InterpreterError::ImmediateWrite)
+        );
+        assert_eq!(interpreter.dump(), code.to_owned());
+
+        let mut interpreter = Interpreter::new("1101,1,2,5,99,0");
+        interpreter.set_strict(true);
+        assert_eq!(interpreter.run_until_halt(), ExecutionState::Exit);
+    }
+
+    #[test]
+    fn test_empty_input_default() {
+        let mut interpreter = Interpreter::new("3,5,4,5,99,0");
+        assert_eq!(interpreter.run_until_halt(), ExecutionState::Wait);
+
+        let mut interpreter = Interpreter::new("3,5,4,5,99,0");
+        interpreter.set_empty_input_default(Some(-1));
+        assert_eq!(interpreter.run_until_halt(), ExecutionState::Exit);
+        assert_eq!(interpreter.get_output_stream(), &[-1]);
+
+        // Pushed input comes first
+        let mut interpreter = Interpreter::new("3,5,4,5,99,0");
+        interpreter.set_empty_input_default(Some(-1));
+        interpreter.push_input(7);
+        interpreter.run_until_halt();
+        assert_eq!(interpreter.get_output_stream(), &[7]);
+    }
+
+    #[test]
+    fn test_run_to_output() {
+        let mut interpreter = Interpreter::new("104,1,104,2,99");
+        assert_eq!(interpreter.run_to_output(), (vec![1], ExecutionState::Next));
+        assert_eq!(interpreter.run_to_output(), (vec![2], ExecutionState::Next));
+        assert_eq!(interpreter.run_to_output(), (vec![], ExecutionState::Exit));
+        assert_eq!(interpreter.dump_output(), "1,2".to_owned());
+
+        let mut interpreter = Interpreter::new("3,0,4,0,99");
+        assert_eq!(interpreter.run_to_output(), (vec![], ExecutionState::Wait));
+    }
+
+    #[test]
+    fn test_dump_with() {
+        let interpreter = Interpreter::new("255,-16");
+        assert_eq!(interpreter.dump_with(",", Radix::Hex), "ff,-10".to_owned());
+        assert_eq!(interpreter.dump_with(" ", Radix::Dec), "255 -16".to_owned());
+        assert_eq!(interpreter.dump(), "255,-16".to_owned());
+    }
+
+    #[test]
+    fn test_steps_executed() {
+        let mut interpreter = Interpreter::new("1,0,0,0,99");
+        interpreter.run();
+        assert_eq!(interpreter.steps_executed(), 2);
+
+        // Calls after exit do not count
+        interpreter.run();
+        interpreter.step();
+        assert_eq!(interpreter.steps_executed(), 2);
+
+        interpreter.reset_intepreter();
+        assert_eq!(interpreter.steps_executed(), 0);
+
+        // Waiting does not count
+        let mut interpreter = Interpreter::new("3,0,99");
+        interpreter.run();
+        interpreter.run();
+        assert_eq!(interpreter.steps_executed(), 0);
+        interpreter.push_input(1);
+        interpreter.run();
+        assert_eq!(interpreter.steps_executed(), 2);
+    }
+
+    #[test]
+    fn test_run_with_deadline() {
+        use std::time::Duration;
+
+        // Infinite loop
+        let mut interpreter = Interpreter::new("1105,1,0");
+        let start = Instant::now();
+        let state = interpreter.run_with_deadline(start + Duration::from_millis(50));
+        assert_eq!(state, ExecutionState::Timeout);
+        assert!(start.elapsed() < Duration::from_secs(2));
+
+        // Resume
+        let deadline = Instant::now() + Duration::from_millis(10);
+        assert_eq!(
+            interpreter.run_with_deadline(deadline),
+            ExecutionState::Timeout
+        );
+
+        let mut interpreter = Interpreter::new("99");
+        assert_eq!(
+            interpreter.run_with_deadline(Instant::now()),
+            ExecutionState::Exit
+        );
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn test_json() {
+        let mut interpreter = Interpreter::new("3,9,8,9,10,9,4,9,99,-1,8");
+        interpreter.push_input(8);
+        interpreter.push_input(3);
+        interpreter.step();
+        interpreter.step();
+
+        let json = interpreter.to_json();
+        assert!(json.contains("\"cursor\":6"));
+        assert_eq!(Interpreter::from_json(&json).unwrap(), interpreter);
+        assert!(Interpreter::from_json("{}").is_err());
+    }
+
+    #[test]
+    fn test_run_interactive() {
+        use std::io::Cursor;
+
+        // Print '?', then echo input until a newline is read
+        let code = "104,63,3,100,4,100,1008,100,10,101,1006,101,2,99";
+        let mut interpreter = Interpreter::new(code);
+        let mut input = Cursor::new("hi\r\n");
+        let mut output: Vec<u8> = vec![];
+        let state = interpreter
+            .run_interactive_with(&mut input, &mut output)
+            .unwrap();
+        assert_eq!(state, ExecutionState::Exit);
+        assert_eq!(String::from_utf8(output).unwrap(), "?hi\n");
+
+        // Exhausted input
+        let mut interpreter = Interpreter::new("3,0,104,1000,99");
+        let mut input = Cursor::new("");
+        let mut output: Vec<u8> = vec![];
+        let state = interpreter
+            .run_interactive_with(&mut input, &mut output)
+            .unwrap();
+        assert_eq!(state, ExecutionState::Wait);
+        assert!(output.is_empty());
+    }
+
     #[test]
     fn test_jumps_and_conditions() {
         // Equals 8 (pos)
         assert_eq!(
-            Interpreter::run_with_input_output("3,9,8,9,10,9,4,9,99,-1,8", &vec![8]),
+            Interpreter::run_with_input_output("3,9,8,9,10,9,4,9,99,-1,8", &[8]),
             "1".to_owned()
         );
         assert_eq!(
-            Interpreter::run_with_input_output("3,9,8,9,10,9,4,9,99,-1,8", &vec![7]),
+            Interpreter::run_with_input_output("3,9,8,9,10,9,4,9,99,-1,8", &[7]),
             "0".to_owned()
         );
         // Less than 8 (pos)
         assert_eq!(
-            Interpreter::run_with_input_output("3,9,7,9,10,9,4,9,99,-1,8", &vec![8]),
+            Interpreter::run_with_input_output("3,9,7,9,10,9,4,9,99,-1,8", &[8]),
             "0".to_owned()
         );
         assert_eq!(
-            Interpreter::run_with_input_output("3,9,7,9,10,9,4,9,99,-1,8", &vec![7]),
+            Interpreter::run_with_input_output("3,9,7,9,10,9,4,9,99,-1,8", &[7]),
             "1".to_owned()
         );
         // Equals 8 (imm)
         assert_eq!(
-            Interpreter::run_with_input_output("3,3,1108,-1,8,3,4,3,99", &vec![8]),
+            Interpreter::run_with_input_output("3,3,1108,-1,8,3,4,3,99", &[8]),
             "1".to_owned()
         );
         assert_eq!(
-            Interpreter::run_with_input_output("3,3,1108,-1,8,3,4,3,99", &vec![7]),
+            Interpreter::run_with_input_output("3,3,1108,-1,8,3,4,3,99", &[7]),
             "0".to_owned()
         );
         // Less than 8 (imm)
         assert_eq!(
-            Interpreter::run_with_input_output("3,3,1107,-1,8,3,4,3,99", &vec![8]),
+            Interpreter::run_with_input_output("3,3,1107,-1,8,3,4,3,99", &[8]),
             "0".to_owned()
         );
         assert_eq!(
-            Interpreter::run_with_input_output("3,3,1107,-1,8,3,4,3,99", &vec![7]),
+            Interpreter::run_with_input_output("3,3,1107,-1,8,3,4,3,99", &[7]),
             "1".to_owned()
         );
 
         // Jump (pos)
         assert_eq!(
-            Interpreter::run_with_input_output(
-                "3,12,6,12,15,1,13,14,13,4,13,99,-1,0,1,9",
-                &vec![0]
-            ),
+            Interpreter::run_with_input_output("3,12,6,12,15,1,13,14,13,4,13,99,-1,0,1,9", &[0]),
             "0".to_owned()
         );
         assert_eq!(
-            Interpreter::run_with_input_output(
-                "3,12,6,12,15,1,13,14,13,4,13,99,-1,0,1,9",
-                &vec![5]
-            ),
+            Interpreter::run_with_input_output("3,12,6,12,15,1,13,14,13,4,13,99,-1,0,1,9", &[5]),
             "1".to_owned()
         );
         // Jump (imm)
         assert_eq!(
-            Interpreter::run_with_input_output("3,3,1105,-1,9,1101,0,0,12,4,12,99,1", &vec![0]),
+            Interpreter::run_with_input_output("3,3,1105,-1,9,1101,0,0,12,4,12,99,1", &[0]),
             "0".to_owned()
         );
         assert_eq!(
-            Interpreter::run_with_input_output("3,3,1105,-1,9,1101,0,0,12,4,12,99,1", &vec![5]),
+            Interpreter::run_with_input_output("3,3,1105,-1,9,1101,0,0,12,4,12,99,1", &[5]),
             "1".to_owned()
         );
 
@@ -495,15 +1352,15 @@ mod tests {
                          46,1101,1000,1,20,4,20,1105,1,46,98,99";
 
         assert_eq!(
-            Interpreter::run_with_input_output(full_code, &vec![7]),
+            Interpreter::run_with_input_output(full_code, &[7]),
             "999".to_owned()
         );
         assert_eq!(
-            Interpreter::run_with_input_output(full_code, &vec![8]),
+            Interpreter::run_with_input_output(full_code, &[8]),
             "1000".to_owned()
         );
         assert_eq!(
-            Interpreter::run_with_input_output(full_code, &vec![9]),
+            Interpreter::run_with_input_output(full_code, &[9]),
             "1001".to_owned()
         );
     }
@@ -534,4 +1391,14 @@ mod tests {
             "1125899906842624".to_owned()
         );
     }
+
+    #[test]
+    fn test_outputs_self() {
+        let mut interp =
+            Interpreter::new("109,1,204,-1,1001,100,1,100,1008,100,16,101,1006,101,0,99");
+        assert!(interp.outputs_self());
+
+        let mut interp = Interpreter::new("104,1125899906842624,99");
+        assert!(!interp.outputs_self());
+    }
 }
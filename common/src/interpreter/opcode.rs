@@ -139,6 +139,49 @@ impl OpCode {
         }
     }
 
+    /// Parse code stream, returning an error instead of panicking
+    /// on unknown opcodes, unknown parameter modes or missing arguments
+    pub fn try_parse(code_stream: &[i64]) -> Result<(Self, usize), String> {
+        let parametered_code = *code_stream.first().ok_or("empty code stream")?;
+        let len = match parametered_code % 100 {
+            1 | 2 | 7 | 8 => 4,
+            5 | 6 => 3,
+            3 | 4 | 9 => 2,
+            99 => 1,
+            code => return Err(format!("unsupported opcode: {}", code)),
+        };
+
+        let mut base = parametered_code / 100;
+        while base > 0 {
+            if base % 10 > 2 {
+                return Err(format!("unsupported parameter mode: {}", base % 10));
+            }
+            base /= 10;
+        }
+
+        if code_stream.len() < len {
+            return Err(format!(
+                "missing arguments: {} < {}",
+                code_stream.len(),
+                len
+            ));
+        }
+
+        Ok(Self::parse(code_stream))
+    }
+
+    /// Get register written by opcode, if any
+    pub fn write_register(&self) -> Option<Register> {
+        match *self {
+            Self::Add(_, _, r)
+            | Self::Multiply(_, _, r)
+            | Self::LessThan(_, _, r)
+            | Self::Equals(_, _, r)
+            | Self::Store(r) => Some(r),
+            _ => None,
+        }
+    }
+
     /// Dump opcode
     pub fn dump(&self) -> String {
         fn show_reg(reg: Register) -> String {
@@ -195,6 +238,10 @@ mod tests {
         );
         assert_eq!(count, 4);
         assert_eq!(code.dump(), "ADD 8, [10], 8");
+        assert_eq!(
+            code.write_register(),
+            Some(Register::new(8, ParameterMode::Position))
+        );
     }
 
     #[test]
@@ -0,0 +1,52 @@
+//! Input helpers
+
+/// Trim surrounding whitespace, including a trailing newline
+pub fn trimmed(input: &str) -> &str {
+    input.trim()
+}
+
+/// Split input on newlines, trimming trailing whitespace and skipping trailing empty lines
+pub fn split_lines(input: &str) -> Vec<&str> {
+    let mut lines: Vec<_> = input.lines().map(str::trim_end).collect();
+    while lines.last() == Some(&"") {
+        lines.pop();
+    }
+
+    lines
+}
+
+/// Split input on commas, trimming each value and skipping empty ones
+pub fn split_csv(input: &str) -> Vec<&str> {
+    input
+        .split(',')
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_trimmed() {
+        assert_eq!(trimmed("123-456\n"), "123-456");
+        assert_eq!(trimmed("123-456\r\n\n"), "123-456");
+        assert_eq!(trimmed("\n"), "");
+    }
+
+    #[test]
+    fn test_split_lines() {
+        assert_eq!(split_lines("a\nb\n"), vec!["a", "b"]);
+        assert_eq!(split_lines("a\r\nb \n\n"), vec!["a", "b"]);
+        assert_eq!(split_lines(" a\n\nb\n"), vec![" a", "", "b"]);
+        assert!(split_lines("\n\n").is_empty());
+    }
+
+    #[test]
+    fn test_split_csv() {
+        assert_eq!(split_csv("1,2,3\n"), vec!["1", "2", "3"]);
+        assert_eq!(split_csv("1, 2,,3,"), vec!["1", "2", "3"]);
+        assert!(split_csv("").is_empty());
+    }
+}
@@ -1,7 +1,10 @@
 //! Advent of code common
 
+pub mod geometry;
 pub mod interpreter;
+pub mod io;
 pub mod math;
+pub mod network;
 
 pub use self::interpreter::Interpreter;
-pub use self::math::{float_eq, float_eq_eps};
+pub use self::math::{bresenham_line, float_eq, float_eq_eps};
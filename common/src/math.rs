@@ -1,5 +1,11 @@
 //! Math helpers
 
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, VecDeque};
+use std::hash::Hash;
+
+use itertools::Itertools;
+
 pub fn float_eq(a: f32, b: f32) -> bool {
     float_eq_eps(a, b, 0.0001)
 }
@@ -54,6 +60,142 @@ pub fn bresenham_line(x1: i32, y1: i32, x2: i32, y2: i32) -> Vec<(i32, i32)> {
     result
 }
 
+/// Get decimal digits of a number, most significant first
+pub fn digits(n: u64) -> Vec<u8> {
+    digits_base(n, 10)
+}
+
+/// Get digits of a number in a specific base, most significant first.
+/// Base must be between 2 and 256, so each digit fits in a `u8`
+pub fn digits_base(n: u64, base: u64) -> Vec<u8> {
+    assert!(
+        (2..=256).contains(&base),
+        "unsupported digit base: {}",
+        base
+    );
+
+    let mut n = n;
+    let mut digits = vec![(n % base) as u8];
+    n /= base;
+
+    while n > 0 {
+        digits.push((n % base) as u8);
+        n /= base;
+    }
+
+    digits.reverse();
+    digits
+}
+
+/// Build number from decimal digits, most significant first
+pub fn from_digits(digits: &[u8]) -> u64 {
+    digits.iter().fold(0, |acc, &d| acc * 10 + u64::from(d))
+}
+
+/// Compute distance from start for each reachable cell, using 4-connectivity.
+/// The start cell is always included
+pub fn flood_fill(
+    start: (i32, i32),
+    passable: impl Fn((i32, i32)) -> bool,
+) -> HashMap<(i32, i32), u32> {
+    let mut distances = HashMap::new();
+    let mut queue = VecDeque::new();
+    distances.insert(start, 0);
+    queue.push_back(start);
+
+    while let Some((x, y)) = queue.pop_front() {
+        let distance = distances[&(x, y)];
+        for next in &[(x, y - 1), (x + 1, y), (x, y + 1), (x - 1, y)] {
+            if !distances.contains_key(next) && passable(*next) {
+                distances.insert(*next, distance + 1);
+                queue.push_back(*next);
+            }
+        }
+    }
+
+    distances
+}
+
+/// Find the cheapest path from start to goal, with its total cost.
+/// `neighbors` returns each reachable node with its move cost, and
+/// `heuristic` must never overestimate the remaining cost
+/// (a zero heuristic gives Dijkstra)
+pub fn astar<Node, N, H>(
+    start: Node,
+    goal: Node,
+    neighbors: N,
+    heuristic: H,
+) -> Option<(u32, Vec<Node>)>
+where
+    Node: Hash + Eq + Clone,
+    N: Fn(Node) -> Vec<(Node, u32)>,
+    H: Fn(&Node) -> u32,
+{
+    // Nodes are referenced by index in the heap, so they do not need `Ord`
+    let mut nodes = vec![start.clone()];
+    let mut costs = HashMap::new();
+    let mut came_from: HashMap<Node, Node> = HashMap::new();
+    let mut open = BinaryHeap::new();
+    costs.insert(start.clone(), 0);
+    open.push(Reverse((heuristic(&start), 0, 0)));
+
+    while let Some(Reverse((_, cost, index))) = open.pop() {
+        let node = nodes[index].clone();
+        if node == goal {
+            let mut path = vec![node];
+            while let Some(previous) = came_from.get(&path[path.len() - 1]) {
+                path.push(previous.clone());
+            }
+            path.reverse();
+
+            return Some((cost, path));
+        }
+
+        // Outdated heap entry
+        if cost > costs[&node] {
+            continue;
+        }
+
+        for (next, step_cost) in neighbors(node.clone()) {
+            let next_cost = cost + step_cost;
+            if costs.get(&next).is_none_or(|&c| next_cost < c) {
+                costs.insert(next.clone(), next_cost);
+                came_from.insert(next.clone(), node.clone());
+                open.push(Reverse((
+                    next_cost + heuristic(&next),
+                    next_cost,
+                    nodes.len(),
+                )));
+                nodes.push(next);
+            }
+        }
+    }
+
+    None
+}
+
+/// Find the permutation of `0..n` maximizing `score`, with its score.
+/// On ties, the lexicographically smallest permutation wins
+pub fn argmax_permutation<T, F>(n: usize, mut score: F) -> (Vec<usize>, T)
+where
+    T: Ord,
+    F: FnMut(&[usize]) -> T,
+{
+    let mut best: Option<(Vec<usize>, T)> = None;
+
+    for permutation in (0..n).permutations(n) {
+        let value = score(&permutation);
+        let is_better = best.as_ref().is_none_or(|(best_perm, max_value)| {
+            value > *max_value || (value == *max_value && permutation < *best_perm)
+        });
+        if is_better {
+            best = Some((permutation, value));
+        }
+    }
+
+    best.expect("at least one permutation")
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -71,5 +213,90 @@ mod tests {
             bresenham_line(x1, y1, x2, y2),
             vec![(0, 0), (1, 1), (2, 2), (2, 3), (3, 4), (4, 5)]
         );
+
+        // Crate root re-export
+        assert_eq!(
+            crate::bresenham_line(0, 0, 2, 0),
+            vec![(0, 0), (1, 0), (2, 0)]
+        );
+    }
+
+    #[test]
+    fn test_flood_fill() {
+        let open = |(x, y)| (0..3).contains(&x) && (0..3).contains(&y);
+        let distances = flood_fill((0, 0), open);
+        assert_eq!(distances.len(), 9);
+        assert_eq!(distances[&(0, 0)], 0);
+        assert_eq!(distances[&(2, 0)], 2);
+        assert_eq!(distances[&(0, 2)], 2);
+        assert_eq!(distances[&(2, 2)], 4);
+        assert_eq!(distances.values().max(), Some(&4));
+
+        // Wall in the middle column, except at the bottom
+        let walled = |(x, y)| open((x, y)) && (x != 1 || y == 2);
+        let distances = flood_fill((0, 0), walled);
+        assert_eq!(distances.len(), 7);
+        assert_eq!(distances[&(2, 0)], 6);
+    }
+
+    #[test]
+    fn test_astar() {
+        // a -1-> b -1-> d, a -5-> d, b -3-> c -1-> d
+        let edges = |node: char| match node {
+            'a' => vec![('b', 1), ('d', 5)],
+            'b' => vec![('c', 3), ('d', 1)],
+            'c' => vec![('d', 1)],
+            _ => vec![],
+        };
+
+        assert_eq!(
+            astar('a', 'd', edges, |_| 0),
+            Some((2, vec!['a', 'b', 'd']))
+        );
+        assert_eq!(astar('c', 'd', edges, |_| 0), Some((1, vec!['c', 'd'])));
+        assert_eq!(astar('a', 'a', edges, |_| 0), Some((0, vec!['a'])));
+        assert_eq!(astar('d', 'a', edges, |_| 0), None);
+
+        // Grid with a manhattan heuristic
+        let grid = |(x, y): (i32, i32)| {
+            vec![(x + 1, y), (x, y + 1)]
+                .into_iter()
+                .filter(|&(nx, ny)| nx <= 3 && ny <= 3)
+                .map(|p| (p, 1))
+                .collect()
+        };
+        let (cost, path) =
+            astar((0, 0), (3, 3), grid, |&(x, y)| ((3 - x) + (3 - y)) as u32).unwrap();
+        assert_eq!(cost, 6);
+        assert_eq!(path.len(), 7);
+    }
+
+    #[test]
+    fn test_digits() {
+        assert_eq!(digits(0), vec![0]);
+        assert_eq!(digits(7), vec![7]);
+        assert_eq!(digits(1_203), vec![1, 2, 0, 3]);
+        assert_eq!(digits_base(0x1f0, 16), vec![1, 15, 0]);
+        assert_eq!(digits_base(0b101, 2), vec![1, 0, 1]);
+        assert_eq!(digits_base(0xff_00, 256), vec![255, 0]);
+
+        assert_eq!(from_digits(&[]), 0);
+        assert_eq!(from_digits(&[0, 4, 2]), 42);
+        for &n in &[0, 9, 10, 123_456, u64::MAX] {
+            assert_eq!(from_digits(&digits(n)), n);
+        }
+    }
+
+    #[test]
+    fn test_argmax_permutation() {
+        let index_sum = |p: &[usize]| p.iter().enumerate().map(|(i, v)| i * v).sum::<usize>();
+        assert_eq!(argmax_permutation(3, index_sum), (vec![0, 1, 2], 5));
+
+        // Ties keep the smallest permutation
+        assert_eq!(argmax_permutation(3, |_| 0), (vec![0, 1, 2], 0));
+        assert_eq!(
+            argmax_permutation(3, |p: &[usize]| p[0] == 2),
+            (vec![2, 0, 1], true)
+        );
     }
 }
@@ -0,0 +1,313 @@
+//! Network module
+
+use std::convert::TryFrom;
+
+use crate::interpreter::{ExecutionState, Interpreter};
+
+/// NAT address
+pub const NAT_ADDRESS: i64 = 255;
+
+/// Packet routed on the network
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Packet {
+    /// Sender address
+    pub from: usize,
+    /// Destination address
+    pub to: i64,
+    pub x: i64,
+    pub y: i64,
+}
+
+impl Packet {
+    /// Drain complete `(address, x, y)` triples sent by `from`.
+    /// A trailing incomplete packet is kept in `outputs`
+    pub fn from_outputs(from: usize, outputs: &mut Vec<i64>) -> Vec<Packet> {
+        let complete_len = outputs.len() - outputs.len() % 3;
+        outputs
+            .drain(..complete_len)
+            .collect::<Vec<_>>()
+            .chunks(3)
+            .map(|packet| Packet {
+                from,
+                to: packet[0],
+                x: packet[1],
+                y: packet[2],
+            })
+            .collect()
+    }
+}
+
+/// Network of interpreters exchanging `(address, x, y)` packets
+#[derive(Debug, Clone)]
+pub struct Network {
+    machines: Vec<Interpreter>,
+    states: Vec<Option<ExecutionState>>,
+    nat_packet: Option<(i64, i64)>,
+    undeliverable: Vec<Packet>,
+}
+
+impl Network {
+    /// Create network of `count` machines running the same code
+    pub fn new(code: &str, count: usize) -> Self {
+        let interpreter = Interpreter::new(code);
+        Self::from_machines((0..count).map(|_| interpreter.clone()).collect())
+    }
+
+    /// Create network from machines.
+    /// Each machine receives its address as first input
+    pub fn from_machines(machines: Vec<Interpreter>) -> Self {
+        let mut machines = machines;
+        for (address, machine) in machines.iter_mut().enumerate() {
+            machine.push_input(address as i64);
+        }
+
+        Self {
+            states: vec![None; machines.len()],
+            machines,
+            nat_packet: None,
+            undeliverable: vec![],
+        }
+    }
+
+    /// Get machine at address
+    pub fn get_machine(&self, address: usize) -> &Interpreter {
+        &self.machines[address]
+    }
+
+    /// Get last packet sent to the NAT address
+    pub fn get_nat_packet(&self) -> Option<(i64, i64)> {
+        self.nat_packet
+    }
+
+    /// Take packets sent to an address without machine
+    pub fn take_undeliverable(&mut self) -> Vec<Packet> {
+        std::mem::take(&mut self.undeliverable)
+    }
+
+    /// Check if every machine waits for input with an empty input queue
+    pub fn is_idle(&self) -> bool {
+        self.machines
+            .iter()
+            .zip(&self.states)
+            .all(|(machine, state)| {
+                machine.get_input_stream().is_empty() && *state == Some(ExecutionState::Wait)
+            })
+    }
+
+    /// Run each machine until it waits, routing its output packets.
+    /// A machine with an empty input queue reads `-1`.
+    ///
+    /// Return the number of routed packets: the network is idle
+    /// when no packet is routed.
+    pub fn step_all(&mut self) -> usize {
+        self.tick().len()
+    }
+
+    /// Run each machine until it waits, routing its output packets.
+    /// A machine with an empty input queue reads `-1`.
+    ///
+    /// Return the routed packets, in sending order.
+    /// Packets sent to an address without machine are kept apart,
+    /// see `take_undeliverable`.
+    pub fn tick(&mut self) -> Vec<Packet> {
+        let mut packets = vec![];
+
+        for index in 0..self.machines.len() {
+            let machine = &mut self.machines[index];
+            if machine.get_input_stream().is_empty() {
+                machine.push_input(-1);
+            }
+            self.states[index] = Some(machine.run_until_halt());
+
+            // Keep any incomplete packet in the output stream
+            let mut sent = vec![];
+            while machine.get_output_stream().len() >= 3 {
+                let mut next = || machine.pop_output().unwrap();
+                sent.push(Packet {
+                    from: index,
+                    to: next(),
+                    x: next(),
+                    y: next(),
+                });
+            }
+
+            for packet in sent {
+                if self.route(packet.to, packet.x, packet.y) {
+                    packets.push(packet);
+                } else {
+                    self.undeliverable.push(packet);
+                }
+            }
+        }
+
+        packets
+    }
+
+    /// Route packet to the NAT or a machine.
+    /// Return false if no machine has this address
+    fn route(&mut self, address: i64, x: i64, y: i64) -> bool {
+        if address == NAT_ADDRESS {
+            self.nat_packet = Some((x, y));
+            return true;
+        }
+
+        let machine = usize::try_from(address)
+            .ok()
+            .and_then(|address| self.machines.get_mut(address));
+        match machine {
+            Some(machine) => {
+                machine.push_input(x);
+                machine.push_input(y);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Send (1, 42, 43), then forward each received packet to the NAT
+    fn sender_code() -> &'static str {
+        "3,100,104,1,104,42,104,43,3,101,1008,101,-1,103,1005,103,8,\
+         3,102,104,255,4,101,4,102,1105,1,8"
+    }
+
+    /// Echo each received packet to address 0
+    fn echo_code() -> &'static str {
+        "3,100,3,101,1008,101,-1,103,1005,103,2,3,102,104,0,4,101,4,102,1105,1,2"
+    }
+
+    #[test]
+    fn test_packet_from_outputs() {
+        let mut outputs = vec![1, 2, 3, 4, 5];
+        assert_eq!(
+            Packet::from_outputs(2, &mut outputs),
+            vec![Packet {
+                from: 2,
+                to: 1,
+                x: 2,
+                y: 3
+            }]
+        );
+        assert_eq!(outputs, vec![4, 5]);
+
+        outputs.push(6);
+        assert_eq!(Packet::from_outputs(2, &mut outputs).len(), 1);
+        assert!(outputs.is_empty());
+    }
+
+    #[test]
+    fn test_exchange() {
+        let mut network = Network::from_machines(vec![
+            Interpreter::new(sender_code()),
+            Interpreter::new(echo_code()),
+        ]);
+
+        // Sender to echo, then echo to sender
+        assert_eq!(network.step_all(), 2);
+        assert_eq!(network.get_nat_packet(), None);
+
+        // Sender to NAT
+        assert_eq!(network.step_all(), 1);
+        assert_eq!(network.get_nat_packet(), Some((42, 43)));
+
+        // Idle
+        assert_eq!(network.step_all(), 0);
+        assert!(network.is_idle());
+    }
+
+    #[test]
+    fn test_tick() {
+        let mut network = Network::from_machines(vec![
+            Interpreter::new(sender_code()),
+            Interpreter::new(echo_code()),
+        ]);
+
+        assert_eq!(
+            network.tick(),
+            vec![
+                Packet {
+                    from: 0,
+                    to: 1,
+                    x: 42,
+                    y: 43
+                },
+                Packet {
+                    from: 1,
+                    to: 0,
+                    x: 42,
+                    y: 43
+                },
+            ]
+        );
+
+        let packets = network.tick();
+        assert_eq!(
+            packets.iter().find(|p| p.to == NAT_ADDRESS),
+            Some(&Packet {
+                from: 0,
+                to: NAT_ADDRESS,
+                x: 42,
+                y: 43
+            })
+        );
+        assert!(network.tick().is_empty());
+    }
+
+    #[test]
+    fn test_undeliverable() {
+        // Send (-5, 1, 2) and (7, 3, 4), then wait for input
+        let code = "3,100,104,-5,104,1,104,2,104,7,104,3,104,4,3,101,1105,1,14";
+        let mut network = Network::new(code, 2);
+
+        assert_eq!(network.step_all(), 0);
+        assert!(network.is_idle());
+        assert_eq!(
+            network.take_undeliverable(),
+            vec![
+                Packet {
+                    from: 0,
+                    to: -5,
+                    x: 1,
+                    y: 2
+                },
+                Packet {
+                    from: 0,
+                    to: 7,
+                    x: 3,
+                    y: 4
+                },
+                Packet {
+                    from: 1,
+                    to: -5,
+                    x: 1,
+                    y: 2
+                },
+                Packet {
+                    from: 1,
+                    to: 7,
+                    x: 3,
+                    y: 4
+                },
+            ]
+        );
+        assert!(network.take_undeliverable().is_empty());
+    }
+
+    #[test]
+    fn test_is_idle() {
+        let mut network = Network::new(echo_code(), 2);
+        assert!(!network.is_idle());
+
+        // Addresses are read, then both machines wait on -1
+        assert_eq!(network.step_all(), 0);
+        assert!(network.is_idle());
+
+        // Packet in flight
+        network.route(1, 5, 6);
+        assert!(!network.is_idle());
+    }
+}
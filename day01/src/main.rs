@@ -1,17 +1,29 @@
 //! Day 1
 
+use common::io::split_lines;
+
 /// Calculate fuel
 fn calculate_fuel(mass: i32) -> i32 {
-    ((mass as f32 / 3.0).floor() - 2.0) as i32
+    calculate_fuel_i64(mass.into()) as i32
 }
 
 /// Calculate fuel recursive
 fn calculate_fuel_recursive(mass: i32) -> i32 {
+    calculate_fuel_recursive_i64(mass.into()) as i32
+}
+
+/// Calculate fuel using integer arithmetic only
+fn calculate_fuel_i64(mass: i64) -> i64 {
+    mass.div_euclid(3) - 2
+}
+
+/// Calculate fuel recursive using integer arithmetic only
+fn calculate_fuel_recursive_i64(mass: i64) -> i64 {
     let mut sum = 0;
     let mut mass = mass;
 
     loop {
-        let fuel = calculate_fuel(mass);
+        let fuel = calculate_fuel_i64(mass);
         if fuel < 0 {
             break;
         }
@@ -23,36 +35,42 @@ fn calculate_fuel_recursive(mass: i32) -> i32 {
     sum
 }
 
-fn part1(input_txt: &str) -> i32 {
-    let mut sum = 0;
-
-    for line in input_txt.split('\n') {
-        let mass: i32 = line.parse().unwrap();
-        sum += calculate_fuel(mass);
-    }
+/// Parse masses from input, skipping empty lines
+fn parse_masses(input_txt: &str) -> Vec<i32> {
+    split_lines(input_txt)
+        .into_iter()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(|line| line.parse().unwrap())
+        .collect()
+}
 
-    sum
+/// Calculate total fuel for masses
+fn total_fuel(masses: impl Iterator<Item = i32>) -> i32 {
+    masses.map(calculate_fuel).sum()
 }
 
-fn part2(input_txt: &str) -> i32 {
-    let mut sum = 0;
+/// Calculate total fuel for masses, recursive
+fn total_fuel_recursive(masses: impl Iterator<Item = i32>) -> i32 {
+    masses.map(calculate_fuel_recursive).sum()
+}
 
-    for line in input_txt.split('\n') {
-        let mass: i32 = line.parse().unwrap();
-        sum += calculate_fuel_recursive(mass);
-    }
+fn part1(input_txt: &str) -> i32 {
+    total_fuel(parse_masses(input_txt).into_iter())
+}
 
-    sum
+fn part2(input_txt: &str) -> i32 {
+    total_fuel_recursive(parse_masses(input_txt).into_iter())
 }
 
 fn main() {
     let input_txt = include_str!("../input.txt");
     println!("[Part 1]");
-    let r = part1(&input_txt);
+    let r = part1(input_txt);
     println!("Result: {}", r);
 
     println!("[Part 2]");
-    let r = part2(&input_txt);
+    let r = part2(input_txt);
     println!("Result: {}", r);
 }
 
@@ -75,10 +93,30 @@ mod tests {
         assert_eq!(calculate_fuel_recursive(100756), 50346);
     }
 
+    #[test]
+    fn test_large_masses() {
+        // Not representable as f32
+        let mass = 33_554_435;
+        let float_fuel = ((mass as f32 / 3.0).floor() - 2.0) as i64;
+        assert_eq!(float_fuel, 11_184_810);
+        assert_eq!(calculate_fuel_i64(mass), 11_184_809);
+
+        assert_eq!(calculate_fuel_i64(10_000_000_000), 3_333_333_331);
+        assert_eq!(calculate_fuel_recursive_i64(100756), 50346);
+        assert_eq!(calculate_fuel_recursive_i64(10_000_000_000), 4_999_999_930);
+    }
+
+    #[test]
+    fn test_parse_masses() {
+        assert_eq!(parse_masses("12\n\n14\n"), vec![12, 14]);
+        assert_eq!(total_fuel(vec![12, 14].into_iter()), 4);
+        assert_eq!(total_fuel_recursive(vec![14, 1969].into_iter()), 968);
+    }
+
     #[test]
     fn test_results() {
         let input_txt = include_str!("../input.txt");
-        assert_eq!(part1(&input_txt), 3279287);
-        assert_eq!(part2(&input_txt), 4916076);
+        assert_eq!(part1(input_txt), 3279287);
+        assert_eq!(part2(input_txt), 4916076);
     }
 }
@@ -29,11 +29,11 @@ fn main() {
     let input_txt = include_str!("../input.txt");
 
     println!("[Part 1]");
-    let r = part1(&input_txt);
+    let r = part1(input_txt);
     println!("Result: {}", r);
 
     println!("[Part 2]");
-    let r = part2(&input_txt, 19_690_720);
+    let r = part2(input_txt, 19_690_720);
     println!("Result: {}", r);
 }
 
@@ -44,7 +44,7 @@ mod tests {
     #[test]
     fn test_results() {
         let input_txt = include_str!("../input.txt");
-        assert_eq!(part1(&input_txt), 3765464);
-        assert_eq!(part2(&input_txt, 19_690_720), 7610);
+        assert_eq!(part1(input_txt), 3765464);
+        assert_eq!(part2(input_txt, 19_690_720), 7610);
     }
 }
@@ -0,0 +1,499 @@
+//! Crossed wires geometry
+
+use std::collections::{HashMap, HashSet};
+
+use common::io::split_csv;
+
+#[derive(Debug, Copy, Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Point {
+    pub x: i32,
+    pub y: i32,
+}
+
+impl Point {
+    pub fn new(x: i32, y: i32) -> Self {
+        Self { x, y }
+    }
+
+    pub fn from_wire(wire: &str) -> Self {
+        let direction = wire.chars().nth(0).unwrap();
+        let amount: i32 = wire[1..].parse().unwrap();
+
+        match direction {
+            'R' => Self { x: amount, y: 0 },
+            'L' => Self { x: -amount, y: 0 },
+            'U' => Self { x: 0, y: amount },
+            'D' => Self { x: 0, y: -amount },
+            _ => unreachable!(),
+        }
+    }
+
+    pub fn manhattan_distance(self, other: Self) -> u32 {
+        (self.x - other.x).unsigned_abs() + (self.y - other.y).unsigned_abs()
+    }
+
+    pub fn chebyshev_distance(self, other: Self) -> u32 {
+        (self.x - other.x)
+            .unsigned_abs()
+            .max((self.y - other.y).unsigned_abs())
+    }
+
+    pub const fn zero() -> Self {
+        Self { x: 0, y: 0 }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Segment {
+    x1: i32,
+    y1: i32,
+    x2: i32,
+    y2: i32,
+}
+
+impl Segment {
+    pub fn new(origin: Point, wire: &str) -> Self {
+        let wire_point = Point::from_wire(wire);
+        Self {
+            x1: origin.x,
+            y1: origin.y,
+            x2: origin.x + wire_point.x,
+            y2: origin.y + wire_point.y,
+        }
+    }
+
+    /// Count steps along the segment.
+    /// Diagonal segments move on both axes at once
+    pub fn steps(&self) -> u32 {
+        self.origin().chebyshev_distance(self.target())
+    }
+
+    #[cfg(test)]
+    fn new_raw(x1: i32, y1: i32, x2: i32, y2: i32) -> Self {
+        Self { x1, y1, x2, y2 }
+    }
+
+    pub fn origin(&self) -> Point {
+        Point::new(self.x1, self.y1)
+    }
+
+    pub fn target(&self) -> Point {
+        Point::new(self.x2, self.y2)
+    }
+
+    pub fn contains_point(&self, point: Point) -> bool {
+        // Colinear with the segment, and inside its bounding box
+        let (x1, y1, x2, y2) = (
+            i64::from(self.x1),
+            i64::from(self.y1),
+            i64::from(self.x2),
+            i64::from(self.y2),
+        );
+        let (px, py) = (i64::from(point.x), i64::from(point.y));
+        let cross = (x2 - x1) * (py - y1) - (y2 - y1) * (px - x1);
+        cross == 0
+            && point.x >= self.x1.min(self.x2)
+            && point.x <= self.x1.max(self.x2)
+            && point.y >= self.y1.min(self.y2)
+            && point.y <= self.y1.max(self.y2)
+    }
+
+    pub fn steps_to_point(&self, point: Point) -> u32 {
+        self.origin().chebyshev_distance(point)
+    }
+
+    /// Get crossing point with another segment.
+    /// Computed with integers: crossings between grid points are ignored
+    pub fn intersect(&self, other: Self) -> Option<Point> {
+        let (x1, y1, x2, y2) = (
+            i64::from(self.x1),
+            i64::from(self.y1),
+            i64::from(self.x2),
+            i64::from(self.y2),
+        );
+        let (x3, y3, x4, y4) = (
+            i64::from(other.x1),
+            i64::from(other.y1),
+            i64::from(other.x2),
+            i64::from(other.y2),
+        );
+
+        let mut d = (y4 - y3) * (x2 - x1) - (x4 - x3) * (y2 - y1);
+        let mut n_a = (x4 - x3) * (y1 - y3) - (y4 - y3) * (x1 - x3);
+        let mut n_b = (x2 - x1) * (y1 - y3) - (y2 - y1) * (x1 - x3);
+        if d == 0 {
+            return None;
+        }
+
+        // Keep the denominator positive, so that 0 <= n / d <= 1 is 0 <= n <= d
+        if d < 0 {
+            d = -d;
+            n_a = -n_a;
+            n_b = -n_b;
+        }
+        if n_a < 0 || n_a > d || n_b < 0 || n_b > d {
+            return None;
+        }
+
+        let (x_num, y_num) = (n_a * (x2 - x1), n_a * (y2 - y1));
+        if x_num % d != 0 || y_num % d != 0 {
+            return None;
+        }
+
+        Some(Point::new((x1 + x_num / d) as i32, (y1 + y_num / d) as i32))
+    }
+}
+
+/// Maximum rendered grid width or height
+const MAX_RENDER_SIZE: i32 = 10_000;
+
+pub struct SegmentPath(Vec<Segment>);
+
+impl SegmentPath {
+    pub fn from_path(path: &str) -> Self {
+        let mut segments = vec![];
+        let mut origin = Point::new(0, 0);
+        for wire in split_csv(path) {
+            let segment = Segment::new(origin, wire);
+            origin = segment.target();
+            segments.push(segment);
+        }
+
+        Self(segments)
+    }
+
+    pub fn count_steps_to_point(&self, point: Point) -> u32 {
+        let mut count = 0;
+        for segment in &self.0 {
+            if segment.contains_point(point) {
+                count += segment.steps_to_point(point);
+                break;
+            } else {
+                count += segment.steps();
+            }
+        }
+
+        count
+    }
+
+    /// Get each point covered by the path, with its wire character
+    pub fn raster_points(&self) -> HashMap<Point, char> {
+        let mut points = HashMap::new();
+        for segment in &self.0 {
+            let dx = (segment.x2 - segment.x1).signum();
+            let dy = (segment.y2 - segment.y1).signum();
+            let c = match (dx, dy) {
+                (_, 0) => '-',
+                (0, _) => '|',
+                _ if dx == dy => '/',
+                _ => '\\',
+            };
+
+            for step in 0..=segment.steps() as i32 {
+                points.insert(
+                    Point::new(segment.x1 + dx * step, segment.y1 + dy * step),
+                    c,
+                );
+            }
+        }
+
+        points
+    }
+
+    /// Render both wires on a character grid, Y pointing up.
+    /// Intersections are shown as `+`, and origin as `o`
+    pub fn render(&self, other: &Self) -> Result<String, String> {
+        // Bounding box from segment endpoints, checked before rasterizing
+        let mut all_points: Vec<Point> = self
+            .0
+            .iter()
+            .chain(other.0.iter())
+            .flat_map(|segment| vec![segment.origin(), segment.target()])
+            .collect();
+        all_points.push(Point::zero());
+
+        let x_min = all_points.iter().map(|p| p.x).min().unwrap();
+        let x_max = all_points.iter().map(|p| p.x).max().unwrap();
+        let y_min = all_points.iter().map(|p| p.y).min().unwrap();
+        let y_max = all_points.iter().map(|p| p.y).max().unwrap();
+
+        let width = i64::from(x_max) - i64::from(x_min) + 1;
+        let height = i64::from(y_max) - i64::from(y_min) + 1;
+        if width > i64::from(MAX_RENDER_SIZE) || height > i64::from(MAX_RENDER_SIZE) {
+            return Err(format!("grid too large: {}x{}", width, height));
+        }
+
+        let first_points = self.raster_points();
+        let second_points = other.raster_points();
+
+        let mut output = String::new();
+        for y in (y_min..=y_max).rev() {
+            for x in x_min..=x_max {
+                let point = Point::new(x, y);
+                let c = match (first_points.get(&point), second_points.get(&point)) {
+                    _ if point == Point::zero() => 'o',
+                    (Some(_), Some(_)) => '+',
+                    (Some(c), None) | (None, Some(c)) => *c,
+                    (None, None) => '.',
+                };
+                output.push(c);
+            }
+
+            if y != y_min {
+                output.push('\n');
+            }
+        }
+
+        Ok(output)
+    }
+
+    /// Get every intersection point between both paths, except origin
+    pub fn intersect_points(&self, other: &Self) -> HashSet<Point> {
+        let mut intersection_points = HashSet::new();
+        for f_path in &self.0 {
+            for s_path in &other.0 {
+                if let Some(p) = f_path.intersect(*s_path) {
+                    if p != Point::zero() {
+                        intersection_points.insert(p);
+                    }
+                }
+            }
+        }
+
+        intersection_points
+    }
+
+    /// Get closest intersection distance and shortest intersection steps
+    pub fn analyze(&self, other: &Self) -> (u32, u32) {
+        self.analyze_from(other, Point::zero())
+    }
+
+    /// Get closest intersection distance from origin and shortest intersection steps,
+    /// in a single intersection pass
+    pub fn analyze_from(&self, other: &Self, origin: Point) -> (u32, u32) {
+        let intersection_points = self.intersect_points(other);
+        let mut closest_distance = u32::MAX;
+        let mut shortest_steps = u32::MAX;
+
+        for p in intersection_points {
+            let dist = origin.manhattan_distance(p);
+            if dist < closest_distance {
+                closest_distance = dist;
+            }
+
+            let first_steps = self.count_steps_to_point(p);
+            let second_steps = other.count_steps_to_point(p);
+            let sum = first_steps + second_steps;
+
+            if sum < shortest_steps {
+                shortest_steps = sum;
+            }
+        }
+
+        (closest_distance, shortest_steps)
+    }
+
+    pub fn closest_intersection_distance(&self, other: &Self, origin: Point) -> u32 {
+        self.analyze_from(other, origin).0
+    }
+
+    pub fn shortest_intersection_steps(&self, other: &Self) -> u32 {
+        self.analyze(other).1
+    }
+}
+
+pub fn calculate_intersection_distance(first_path: &str, second_path: &str) -> u32 {
+    let first_seg_path = SegmentPath::from_path(first_path);
+    let second_seg_path = SegmentPath::from_path(second_path);
+
+    first_seg_path.closest_intersection_distance(&second_seg_path, Point::zero())
+}
+
+pub fn calculate_shortest_intersection_steps(first_path: &str, second_path: &str) -> u32 {
+    let first_seg_path = SegmentPath::from_path(first_path);
+    let second_seg_path = SegmentPath::from_path(second_path);
+
+    first_seg_path.shortest_intersection_steps(&second_seg_path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_segments() {
+        assert_eq!(
+            SegmentPath::from_path("R8,U5,L5,D3").0,
+            vec![
+                Segment::new_raw(0, 0, 8, 0),
+                Segment::new_raw(8, 0, 8, 5),
+                Segment::new_raw(8, 5, 3, 5),
+                Segment::new_raw(3, 5, 3, 2)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_contains() {
+        assert!(Segment::new_raw(3, 5, 3, 2).contains_point(Point::new(3, 3)));
+        assert!(!Segment::new_raw(3, 5, 3, 2).contains_point(Point::new(3, 1)));
+        assert!(!Segment::new_raw(3, 5, 3, 2).contains_point(Point::new(4, 3)));
+        assert!(Segment::new_raw(0, 0, 100_000, 100_000).contains_point(Point::new(60_000, 60_000)));
+        assert!(!Segment::new_raw(0, 0, 100_000, 100_000).contains_point(Point::new(60_000, 0)));
+    }
+
+    #[test]
+    fn test_diagonal() {
+        let segment = Segment::new_raw(1, 1, 4, -2);
+        assert_eq!(segment.steps(), 3);
+        assert!(segment.contains_point(Point::new(2, 0)));
+        assert!(segment.contains_point(Point::new(4, -2)));
+        assert!(!segment.contains_point(Point::new(2, -1)));
+        assert!(!segment.contains_point(Point::new(5, -3)));
+        assert_eq!(segment.steps_to_point(Point::new(3, -1)), 2);
+
+        let path = SegmentPath(vec![
+            Segment::new_raw(0, 0, 2, 2),
+            Segment::new_raw(2, 2, 5, -1),
+        ]);
+        assert_eq!(path.count_steps_to_point(Point::new(3, 1)), 3);
+        assert_eq!(path.raster_points()[&Point::new(1, 1)], '/');
+        assert_eq!(path.raster_points()[&Point::new(3, 1)], '\\');
+    }
+
+    #[test]
+    fn test_intersect() {
+        assert_eq!(
+            Segment::new_raw(0, 0, 4, 0).intersect(Segment::new_raw(2, -2, 2, 2)),
+            Some(Point::new(2, 0))
+        );
+
+        // Diagonal crossings
+        assert_eq!(
+            Segment::new_raw(0, 0, 2, 2).intersect(Segment::new_raw(0, 2, 2, 0)),
+            Some(Point::new(1, 1))
+        );
+        assert_eq!(
+            Segment::new_raw(0, 0, 1, 1).intersect(Segment::new_raw(0, 1, 1, 0)),
+            None
+        );
+        assert_eq!(
+            Segment::new_raw(0, 0, 4, 0).intersect(Segment::new_raw(5, -2, 5, 2)),
+            None
+        );
+    }
+
+    #[test]
+    fn test_intersect_points() {
+        let first_path = SegmentPath::from_path("R8,U5,L5,D3");
+        let second_path = SegmentPath::from_path("U7,R6,D4,L4");
+        let expected: HashSet<Point> = [Point::new(3, 3), Point::new(6, 5)]
+            .iter()
+            .copied()
+            .collect();
+        assert_eq!(first_path.intersect_points(&second_path), expected);
+        assert_eq!(second_path.intersect_points(&first_path), expected);
+    }
+
+    #[test]
+    fn test_intersection() {
+        assert_eq!(
+            calculate_intersection_distance("R8,U5,L5,D3", "U7,R6,D4,L4"),
+            6
+        );
+        assert_eq!(
+            calculate_intersection_distance(
+                "R75,D30,R83,U83,L12,D49,R71,U7,L72",
+                "U62,R66,U55,R34,D71,R55,D58,R83"
+            ),
+            159
+        );
+        assert_eq!(
+            calculate_intersection_distance(
+                "R98,U47,R26,D63,R33,U87,L62,D20,R33,U53,R51",
+                "U98,R91,D20,R16,D67,R40,U7,R15,U6,R7"
+            ),
+            135
+        );
+    }
+
+    #[test]
+    fn test_steps() {
+        assert_eq!(
+            calculate_shortest_intersection_steps("R8,U5,L5,D3", "U7,R6,D4,L4"),
+            30
+        );
+        assert_eq!(
+            calculate_shortest_intersection_steps(
+                "R75,D30,R83,U83,L12,D49,R71,U7,L72",
+                "U62,R66,U55,R34,D71,R55,D58,R83"
+            ),
+            610
+        );
+        assert_eq!(
+            calculate_shortest_intersection_steps(
+                "R98,U47,R26,D63,R33,U87,L62,D20,R33,U53,R51",
+                "U98,R91,D20,R16,D67,R40,U7,R15,U6,R7"
+            ),
+            410
+        );
+    }
+
+    #[test]
+    fn test_render() {
+        let first_path = SegmentPath::from_path("R8,U5,L5,D3");
+        let second_path = SegmentPath::from_path("U7,R6,D4,L4");
+        let output = first_path.render(&second_path).unwrap();
+
+        assert_eq!(
+            output,
+            "------|..\n\
+             |.....|..\n\
+             |..|--+--\n\
+             |..|..|.|\n\
+             |.-+---.|\n\
+             |..|....|\n\
+             |.......|\n\
+             o-------|"
+        );
+        assert_eq!(output.matches('o').count(), 1);
+        assert_eq!(output.matches('+').count(), 2);
+
+        let far_path = SegmentPath::from_path("R20000");
+        assert!(far_path.render(&second_path).is_err());
+
+        // Rejected without rasterizing
+        let huge_path = SegmentPath::from_path("R2000000000,L2000000000,L2000000000");
+        assert_eq!(
+            huge_path.render(&second_path),
+            Err("grid too large: 4000000001x8".to_owned())
+        );
+    }
+
+    #[test]
+    fn test_analyze() {
+        let examples = [
+            ("R8,U5,L5,D3", "U7,R6,D4,L4"),
+            (
+                "R75,D30,R83,U83,L12,D49,R71,U7,L72",
+                "U62,R66,U55,R34,D71,R55,D58,R83",
+            ),
+            (
+                "R98,U47,R26,D63,R33,U87,L62,D20,R33,U53,R51",
+                "U98,R91,D20,R16,D67,R40,U7,R15,U6,R7",
+            ),
+        ];
+
+        for (first, second) in examples.iter() {
+            let first_path = SegmentPath::from_path(first);
+            let second_path = SegmentPath::from_path(second);
+            assert_eq!(
+                first_path.analyze(&second_path),
+                (
+                    calculate_intersection_distance(first, second),
+                    calculate_shortest_intersection_steps(first, second)
+                )
+            );
+        }
+    }
+}
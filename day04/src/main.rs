@@ -1,87 +1,85 @@
+use std::env;
+
+use common::io::trimmed;
+use common::math::{digits, digits_base};
+
 /// Check if input is in range
-fn check_range(input: u32, min_range: u32, max_range: u32) -> bool {
+fn check_range(input: u64, min_range: u64, max_range: u64) -> bool {
     input >= min_range && input <= max_range
 }
 
 /// Check if input has same adjacents digits and that it never decreases
-fn check_digits(input: u32) -> bool {
-    let mut inp = input;
-    let mut last_digit = None;
-    let mut same_digits = false;
-
-    while inp > 0 {
-        let digit = (inp % 10) as i32;
-        if let Some(l) = last_digit {
-            // Same digit? Can be valid.
-            if digit == l {
-                same_digits = true;
-            }
+fn check_digits(input: u64) -> bool {
+    check_digits_base(input, 10)
+}
 
-            // Next digit is bigger than last? Invalid.
-            if digit > l {
-                return false;
-            }
-        }
+/// Check if input has same adjacents digits and that it never decreases,
+/// in a specific base
+fn check_digits_base(input: u64, base: u64) -> bool {
+    let digits = digits_base(input, base);
 
-        last_digit = Some(digit);
-        inp /= 10;
+    // Next digit is smaller than last? Invalid.
+    if digits.windows(2).any(|w| w[1] < w[0]) {
+        return false;
     }
 
-    same_digits
+    // Same digit? Valid.
+    digits.windows(2).any(|w| w[1] == w[0])
 }
 
-/// Check if input has same adjacents digits (but not more than 2)
-/// and that it never decreases
-fn check_digits_non_repeated(input: u32) -> bool {
-    let mut inp = input;
-    let mut last_digit = None;
-    let mut last_repeated_count = 0;
-    let mut same_digits = false;
-
-    while inp > 0 {
-        let digit = (inp % 10) as i32;
-        if let Some(l) = last_digit {
-            // Same digit? Can be valid.
-            if digit == l {
-                last_repeated_count += 1;
-            } else {
-                if last_repeated_count == 1 {
-                    same_digits = true;
-                }
-
-                last_repeated_count = 0;
-            }
+/// Check if input digits never decrease
+fn never_decreases(input: u64) -> bool {
+    never_decreases_base(input, 10)
+}
+
+/// Check if input digits never decrease, in a specific base
+fn never_decreases_base(input: u64, base: u64) -> bool {
+    digits_base(input, base).windows(2).all(|w| w[0] <= w[1])
+}
+
+/// Check if input has a run of exactly `k` same adjacent digits
+fn has_exact_run(input: u64, k: usize) -> bool {
+    has_exact_run_base(input, k, 10)
+}
 
-            // Next digit is bigger than last? Invalid.
-            if digit > l {
-                return false;
+/// Check if input has a run of exactly `k` same adjacent digits, in a specific base
+fn has_exact_run_base(input: u64, k: usize, base: u64) -> bool {
+    let digits = digits_base(input, base);
+    let mut run_length = 1;
+    for w in digits.windows(2) {
+        if w[0] == w[1] {
+            run_length += 1;
+        } else {
+            if run_length == k {
+                return true;
             }
-        }
 
-        last_digit = Some(digit);
-        inp /= 10;
+            run_length = 1;
+        }
     }
 
-    // Handle last digit
-    if last_repeated_count == 1 {
-        same_digits = true;
-    }
+    // Handle last run
+    run_length == k
+}
 
-    same_digits
+/// Check if input has same adjacents digits (but not more than 2)
+/// and that it never decreases
+fn check_digits_non_repeated(input: u64) -> bool {
+    never_decreases(input) && has_exact_run(input, 2)
 }
 
 /// Check if an input is valid
-fn check_valid_input(input: u32, min_range: u32, max_range: u32) -> bool {
+fn check_valid_input(input: u64, min_range: u64, max_range: u64) -> bool {
     check_range(input, min_range, max_range) && check_digits(input)
 }
 
 /// Check if an input is valid (non-repeated)
-fn check_valid_input_non_repeated(input: u32, min_range: u32, max_range: u32) -> bool {
+fn check_valid_input_non_repeated(input: u64, min_range: u64, max_range: u64) -> bool {
     check_range(input, min_range, max_range) && check_digits_non_repeated(input)
 }
 
 /// Count valid passwords in range
-fn count_valid_passwords(min_range: u32, max_range: u32) -> u32 {
+fn count_valid_passwords(min_range: u64, max_range: u64) -> u64 {
     let mut count = 0;
     for x in min_range..=max_range {
         if check_valid_input(x, min_range, max_range) {
@@ -93,7 +91,7 @@ fn count_valid_passwords(min_range: u32, max_range: u32) -> u32 {
 }
 
 /// Count valid passwords in range, non-repeated
-fn count_valid_passwords_non_repeated(min_range: u32, max_range: u32) -> u32 {
+fn count_valid_passwords_non_repeated(min_range: u64, max_range: u64) -> u64 {
     let mut count = 0;
     for x in min_range..=max_range {
         if check_valid_input_non_repeated(x, min_range, max_range) {
@@ -104,25 +102,111 @@ fn count_valid_passwords_non_repeated(min_range: u32, max_range: u32) -> u32 {
     count
 }
 
-fn part1(input_txt: &str) -> u32 {
-    let entries: Vec<u32> = input_txt.split('-').map(|x| x.parse().unwrap()).collect();
-    count_valid_passwords(entries[0], entries[1])
+/// Compute binomial coefficient `n choose k`
+fn binomial(n: u64, k: u64) -> u64 {
+    if k > n {
+        return 0;
+    }
+
+    (0..k).fold(1, |acc, i| acc * (n - i) / (i + 1))
+}
+
+/// Count non-decreasing digit suffixes of length `remaining`, following a `last` digit,
+/// which make the whole number have same adjacent digits
+fn count_valid_suffixes(last: u64, has_pair: bool, remaining: u64) -> u64 {
+    // Non-decreasing suffixes are multisets of digits in last..=9
+    let total = binomial(remaining + 9 - last, remaining);
+    if has_pair {
+        total
+    } else {
+        // Suffixes without same adjacent digits are strictly increasing, above last
+        total - binomial(9 - last, remaining)
+    }
+}
+
+/// Count valid passwords in 1..=max_range, without enumerating them
+fn count_valid_passwords_up_to(max_range: u64) -> u64 {
+    if max_range == 0 {
+        return 0;
+    }
+
+    let max_digits: Vec<u64> = digits(max_range).into_iter().map(u64::from).collect();
+    let len = max_digits.len() as u64;
+    let mut count = 0;
+
+    // Shorter numbers
+    for k in 1..len {
+        for first in 1..=9 {
+            count += count_valid_suffixes(first, false, k - 1);
+        }
+    }
+
+    // Same length numbers, lower than max_range on their first differing digit
+    let mut last = 1;
+    let mut has_pair = false;
+    for (i, &digit) in max_digits.iter().enumerate() {
+        let remaining = len - 1 - i as u64;
+        for d in last..digit {
+            count += count_valid_suffixes(d, has_pair || (i > 0 && d == last), remaining);
+        }
+
+        if digit < last {
+            return count;
+        }
+
+        has_pair = has_pair || (i > 0 && digit == last);
+        last = digit;
+    }
+
+    // max_range itself
+    if has_pair {
+        count += 1;
+    }
+
+    count
+}
+
+/// Count valid passwords in range using combinatorics
+fn count_valid_passwords_fast(min_range: u64, max_range: u64) -> u64 {
+    if min_range > max_range {
+        return 0;
+    }
+
+    count_valid_passwords_up_to(max_range)
+        - count_valid_passwords_up_to(min_range.saturating_sub(1))
 }
 
-fn part2(input_txt: &str) -> u32 {
-    let entries: Vec<u32> = input_txt.split('-').map(|x| x.parse().unwrap()).collect();
+fn part1(input_txt: &str, brute_force: bool) -> u64 {
+    let entries: Vec<u64> = trimmed(input_txt)
+        .split('-')
+        .map(|x| x.parse().unwrap())
+        .collect();
+    if brute_force {
+        count_valid_passwords(entries[0], entries[1])
+    } else {
+        count_valid_passwords_fast(entries[0], entries[1])
+    }
+}
+
+fn part2(input_txt: &str) -> u64 {
+    let entries: Vec<u64> = trimmed(input_txt)
+        .split('-')
+        .map(|x| x.parse().unwrap())
+        .collect();
     count_valid_passwords_non_repeated(entries[0], entries[1])
 }
 
 fn main() {
     let input_txt = include_str!("../input.txt");
+    let args: Vec<String> = env::args().collect();
+    let brute_force = &args.get(1).cloned().unwrap_or_else(|| "".to_owned()) == "brute";
 
     println!("[Part 1]");
-    let r = part1(&input_txt);
+    let r = part1(input_txt, brute_force);
     println!("Result: {}", r);
 
     println!("[Part 2]");
-    let r = part2(&input_txt);
+    let r = part2(input_txt);
     println!("Result: {}", r);
 }
 
@@ -152,6 +236,38 @@ mod tests {
         assert!(!check_valid_input_non_repeated(124_444, 100_000, 999_999));
     }
 
+    #[test]
+    fn test_valid_base() {
+        assert!(check_digits_base(0x11, 16));
+        assert!(check_digits_base(0xff, 16));
+        assert!(check_digits_base(0x1ff, 16));
+        assert!(!check_digits_base(0x1f, 16));
+        assert!(!check_digits_base(0xf0, 16));
+        assert!(never_decreases_base(0x1ff, 16));
+        assert!(has_exact_run_base(0x1ff, 2, 16));
+        assert!(!has_exact_run_base(0xfff, 2, 16));
+
+        let count = (0x10..=0x30).filter(|&x| check_digits_base(x, 16)).count();
+        assert_eq!(count, 2);
+    }
+
+    #[test]
+    fn test_exact_run() {
+        assert!(has_exact_run(111_223, 3));
+        assert!(has_exact_run(111_223, 2));
+        assert!(!has_exact_run(111_123, 3));
+        assert!(has_exact_run(111_123, 4));
+        // Final run
+        assert!(has_exact_run(123_444, 3));
+        assert!(!has_exact_run(123_444, 2));
+        assert!(!has_exact_run(124_444, 3));
+        // First run
+        assert!(has_exact_run(444_123, 3));
+        assert!(has_exact_run(111_111, 6));
+        assert!(!has_exact_run(111_111, 0));
+        assert!(has_exact_run(0, 1));
+    }
+
     #[test]
     fn test_password_count() {
         assert_eq!(count_valid_passwords(100_000, 100_010), 0);
@@ -171,10 +287,64 @@ mod tests {
         assert_eq!(count_valid_passwords_non_repeated(100_000, 111_223), 9);
     }
 
+    #[test]
+    fn test_password_count_fast() {
+        let ranges = [
+            (100_000, 100_010),
+            (100_000, 111_111),
+            (100_000, 111_112),
+            (0, 2_000),
+            (11, 11),
+            (12, 10),
+            (123_456, 234_567),
+        ];
+
+        for &(min_range, max_range) in ranges.iter() {
+            assert_eq!(
+                count_valid_passwords_fast(min_range, max_range),
+                count_valid_passwords(min_range, max_range)
+            );
+        }
+
+        // Each upper bound up to 1000
+        for max_range in 0..1_000 {
+            assert_eq!(
+                count_valid_passwords_fast(0, max_range),
+                count_valid_passwords(0, max_range)
+            );
+        }
+
+        let input_txt = include_str!("../input.txt");
+        let entries: Vec<u64> = trimmed(input_txt)
+            .split('-')
+            .map(|x| x.parse().unwrap())
+            .collect();
+        assert_eq!(
+            count_valid_passwords_fast(entries[0], entries[1]),
+            count_valid_passwords(entries[0], entries[1])
+        );
+    }
+
+    #[test]
+    fn test_password_count_large() {
+        let min_range = 4_444_444_444;
+        let max_range = 4_444_444_455;
+        assert!(min_range > u64::from(u32::MAX));
+
+        assert!(check_valid_input(4_444_444_449, min_range, max_range));
+        assert!(!check_valid_input(4_444_444_450, min_range, max_range));
+        assert_eq!(count_valid_passwords(min_range, max_range), 7);
+        assert_eq!(count_valid_passwords_fast(min_range, max_range), 7);
+        assert_eq!(count_valid_passwords_non_repeated(min_range, max_range), 1);
+        assert_eq!(part1("4444444444-4444444455", false), 7);
+        assert_eq!(part2("4444444444-4444444455"), 1);
+    }
+
     #[test]
     fn test_results() {
         let input_txt = include_str!("../input.txt");
-        assert_eq!(part1(&input_txt), 1169);
-        assert_eq!(part2(&input_txt), 757);
+        assert_eq!(part1(input_txt, false), 1169);
+        assert_eq!(part1(input_txt, true), 1169);
+        assert_eq!(part2(input_txt), 757);
     }
 }
@@ -18,11 +18,11 @@ fn main() {
     let input_txt = include_str!("../input.txt");
 
     println!("[Part 1]");
-    let r = part1(&input_txt);
+    let r = part1(input_txt);
     println!("Result: {}", r);
 
     println!("[Part 2]");
-    let r = part2(&input_txt);
+    let r = part2(input_txt);
     println!("Result: {}", r);
 }
 
@@ -33,7 +33,7 @@ mod tests {
     #[test]
     fn test_results() {
         let input_txt = include_str!("../input.txt");
-        assert_eq!(part1(&input_txt), 12428642);
-        assert_eq!(part2(&input_txt), 918655);
+        assert_eq!(part1(input_txt), 12428642);
+        assert_eq!(part2(input_txt), 918655);
     }
 }
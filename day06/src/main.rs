@@ -1,19 +1,63 @@
 use std::collections::HashMap;
 
+use common::io::split_lines;
+
 #[derive(Debug)]
 pub struct OrbitGraph {
     nodes: HashMap<String, String>,
+    children: HashMap<String, Vec<String>>,
 }
 
 impl OrbitGraph {
     pub fn new(input_txt: &str) -> Self {
         let mut nodes = HashMap::new();
-        for line in input_txt.split('\n') {
+        let mut children: HashMap<String, Vec<String>> = HashMap::new();
+        for line in split_lines(input_txt) {
             let entry: Vec<&str> = line.split(')').collect();
             nodes.insert(entry[1].to_owned(), entry[0].to_owned());
+            children
+                .entry(entry[0].to_owned())
+                .or_default()
+                .push(entry[1].to_owned());
+        }
+
+        Self { nodes, children }
+    }
+
+    /// List direct children of node
+    pub fn children_of(&self, node: &str) -> Vec<String> {
+        self.children.get(node).cloned().unwrap_or_default()
+    }
+
+    /// Get deepest orbit chain length
+    pub fn max_depth(&self) -> usize {
+        self.orbit_depths_with(|_| ())
+            .values()
+            .max()
+            .copied()
+            .unwrap_or(0)
+    }
+
+    /// Hash orbit edges in sorted order, independent of input line order.
+    /// Uses FNV-1a, to stay stable across Rust versions
+    pub fn checksum(&self) -> u64 {
+        let mut edges: Vec<(&str, &str)> = self
+            .nodes
+            .iter()
+            .map(|(child, parent)| (parent.as_str(), child.as_str()))
+            .collect();
+        edges.sort_unstable();
+
+        let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+        for (parent, child) in edges {
+            // Separators keep ("AB", "C") and ("A", "BC") apart
+            for byte in format!("{}){}\n", parent, child).bytes() {
+                hash ^= u64::from(byte);
+                hash = hash.wrapping_mul(0x0100_0000_01b3);
+            }
         }
 
-        Self { nodes }
+        hash
     }
 
     /// List orbits at point
@@ -22,8 +66,7 @@ impl OrbitGraph {
         let mut stack = vec![];
         stack.push(point);
 
-        while !stack.is_empty() {
-            let pt = stack.pop().unwrap();
+        while let Some(pt) = stack.pop() {
             if let Some(source) = self.nodes.get(pt) {
                 orbits.push(source.to_owned());
                 stack.push(source);
@@ -40,12 +83,41 @@ impl OrbitGraph {
 
     /// Count total orbits
     pub fn count_total_orbits(&self) -> usize {
-        let mut counter = 0;
+        self.orbit_depths_with(|_| ()).values().sum()
+    }
+
+    /// Compute orbit count of each node, computing each depth once:
+    /// depth(node) = 1 + depth(parent).
+    /// `on_compute` is called for each computed depth
+    fn orbit_depths_with<F>(&self, mut on_compute: F) -> HashMap<&str, usize>
+    where
+        F: FnMut(&str),
+    {
+        let mut depths: HashMap<&str, usize> = HashMap::new();
+
         for key in self.nodes.keys() {
-            counter += self.count_orbits_at_point(key);
+            // Walk up until a known depth or the root
+            let mut path = vec![];
+            let mut point = key.as_str();
+            while !depths.contains_key(point) {
+                match self.nodes.get(point) {
+                    Some(parent) => {
+                        path.push(point);
+                        point = parent;
+                    }
+                    None => break,
+                }
+            }
+
+            let mut depth = depths.get(point).copied().unwrap_or(0);
+            for node in path.into_iter().rev() {
+                depth += 1;
+                on_compute(node);
+                depths.insert(node, depth);
+            }
         }
 
-        counter
+        depths
     }
 
     /// List transfers to target
@@ -65,7 +137,7 @@ impl OrbitGraph {
             }
         }
 
-        if intersection == "" {
+        if intersection.is_empty() {
             // No intersection found, no path
             return vec![];
         }
@@ -108,11 +180,11 @@ fn main() {
     let input_txt = include_str!("../input.txt");
 
     println!("[Part 1]");
-    let r = part1(&input_txt);
+    let r = part1(input_txt);
     println!("Result: {}", r);
 
     println!("[Part 2]");
-    let r = part2(&input_txt);
+    let r = part2(input_txt);
     println!("Result: {}", r);
 }
 
@@ -179,12 +251,54 @@ mod tests {
         orbits_count_eq("COM", 0);
     }
 
+    #[test]
+    fn test_checksum() {
+        let graph = OrbitGraph::new(input_part1());
+        let mut lines: Vec<&str> = input_part1().split('\n').collect();
+        lines.reverse();
+        let reversed_graph = OrbitGraph::new(&lines.join("\n"));
+        assert_eq!(graph.checksum(), reversed_graph.checksum());
+
+        assert_ne!(graph.checksum(), OrbitGraph::new(input_part2()).checksum());
+        assert_ne!(
+            OrbitGraph::new("AB)C").checksum(),
+            OrbitGraph::new("A)BC").checksum()
+        );
+    }
+
     #[test]
     fn test_orbits_total_count() {
         let graph = OrbitGraph::new(input_part1());
         assert_eq!(graph.count_total_orbits(), 42);
     }
 
+    #[test]
+    fn test_orbits_total_count_memoized() {
+        let input: Vec<String> = (0..500).map(|i| format!("N{})N{}", i, i + 1)).collect();
+        let graph = OrbitGraph::new(&input.join("\n"));
+
+        let mut computed = 0;
+        let depths = graph.orbit_depths_with(|_| computed += 1);
+        assert_eq!(depths["N500"], 500);
+        assert_eq!(computed, 500);
+        assert_eq!(graph.count_total_orbits(), 500 * 501 / 2);
+
+        let graph = OrbitGraph::new(input_part1());
+        let mut computed = 0;
+        graph.orbit_depths_with(|_| computed += 1);
+        assert_eq!(computed, 11);
+    }
+
+    #[test]
+    fn test_children_and_depth() {
+        let graph = OrbitGraph::new(input_part1());
+        let mut children = graph.children_of("B");
+        children.sort();
+        assert_eq!(children, vec!["C".to_owned(), "G".to_owned()]);
+        assert!(graph.children_of("L").is_empty());
+        assert_eq!(graph.max_depth(), 7);
+    }
+
     #[test]
     fn test_transfers() {
         let graph = OrbitGraph::new(input_part2());
@@ -203,7 +317,7 @@ mod tests {
     #[test]
     fn test_results() {
         let input_txt = include_str!("../input.txt");
-        assert_eq!(part1(&input_txt), 147807);
-        assert_eq!(part2(&input_txt), 229);
+        assert_eq!(part1(input_txt), 147807);
+        assert_eq!(part2(input_txt), 229);
     }
 }
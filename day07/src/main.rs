@@ -1,12 +1,17 @@
 use common::interpreter::{ExecutionState, Interpreter};
+use common::math::argmax_permutation;
 use itertools::Itertools;
+use std::ops::Range;
+
+/// Amplifier count
+const AMP_COUNT: i64 = 5;
 
 #[derive(Debug, Default)]
 pub struct AmplifierSystem;
 
 impl AmplifierSystem {
     pub fn new() -> Self {
-        Self::default()
+        Self
     }
 
     /// Run interpreter for amp phase.
@@ -20,26 +25,44 @@ impl AmplifierSystem {
         // Push amp input
         interpreter.push_input(input);
 
-        // Run interpreter
-        interpreter.run();
+        // Run interpreter, without dumping each instruction
+        interpreter.run_until_halt();
 
         // Get output
         interpreter.pop_output().unwrap()
     }
 
-    /// Run interpreter for phase sequence
-    pub fn run_phase_sequence(&self, interpreter: &mut Interpreter, phase_sequence: &str) -> i64 {
+    /// Parse phase sequence, checking that it is a permutation of `phases`
+    fn parse_phase_sequence(phase_sequence: &str, phases: Range<i64>) -> Result<Vec<i64>, String> {
+        let error = || format!("invalid phase sequence: {}", phase_sequence);
         let seq: Vec<i64> = phase_sequence
             .split(',')
-            .map(|x| x.parse().unwrap())
-            .collect();
+            .map(|x| x.trim().parse().map_err(|_| error()))
+            .collect::<Result<_, _>>()?;
+
+        let mut sorted = seq.clone();
+        sorted.sort_unstable();
+        if sorted != phases.collect::<Vec<_>>() {
+            return Err(error());
+        }
+
+        Ok(seq)
+    }
+
+    /// Run interpreter for phase sequence
+    pub fn run_phase_sequence(
+        &self,
+        interpreter: &mut Interpreter,
+        phase_sequence: &str,
+    ) -> Result<i64, String> {
+        let seq = Self::parse_phase_sequence(phase_sequence, 0..AMP_COUNT)?;
         let mut output = 0;
 
         for i in seq {
             output = self.run_phase(interpreter, i, output);
         }
 
-        output
+        Ok(output)
     }
 
     /// Run interpreter for feedback phase sequence
@@ -47,28 +70,38 @@ impl AmplifierSystem {
         &self,
         interpreter: &mut Interpreter,
         phase_sequence: &str,
-    ) -> i64 {
-        let mut seq: Vec<i64> = phase_sequence
-            .split(',')
-            .map(|x| x.parse().unwrap())
-            .collect();
-        let mut interpreters: Vec<_> = (0..5).map(|_| interpreter.clone()).collect();
+    ) -> Result<i64, String> {
+        self.run_feedback_detailed(interpreter, phase_sequence)
+            .map(|(signal, _)| signal)
+    }
+
+    /// Run interpreter for feedback phase sequence.
+    /// Output the thruster signal and the last value emitted by each amplifier
+    pub fn run_feedback_detailed(
+        &self,
+        interpreter: &mut Interpreter,
+        phase_sequence: &str,
+    ) -> Result<(i64, Vec<i64>), String> {
+        let mut seq = Self::parse_phase_sequence(phase_sequence, AMP_COUNT..AMP_COUNT * 2)?;
+        // Reset once, then fork each amplifier
+        interpreter.reset_intepreter();
+        let mut interpreters: Vec<_> = (0..AMP_COUNT).map(|_| interpreter.clone()).collect();
 
         // Initialization
         for interp in interpreters.iter_mut() {
-            interp.reset_intepreter();
             interp.push_input(seq.remove(0));
         }
 
-        // Last output
+        // Last output, overall and per amplifier
         let mut last_output = 0;
+        let amp_count = interpreters.len();
+        let mut amp_outputs = vec![0; amp_count];
 
         // Run
         'outer: loop {
-            for index in 0..5 {
+            for (index, interp) in interpreters.iter_mut().enumerate() {
                 // Run interpreter
                 {
-                    let interp = interpreters.get_mut(index).unwrap();
                     interp.push_input(last_output);
 
                     'inner: loop {
@@ -76,17 +109,22 @@ impl AmplifierSystem {
                         match state {
                             ExecutionState::Wait => {
                                 last_output = interp.pop_output().unwrap();
+                                amp_outputs[index] = last_output;
                                 break 'inner;
                             }
                             ExecutionState::Exit => {
                                 last_output = interp.pop_output().unwrap();
+                                amp_outputs[index] = last_output;
                                 // Last index?
-                                if index == 4 {
+                                if index == amp_count - 1 {
                                     break 'outer;
                                 } else {
                                     break 'inner;
                                 }
                             }
+                            ExecutionState::Error(_) | ExecutionState::Timeout => {
+                                panic!("unexpected interpreter state: {:?}", state);
+                            }
                             ExecutionState::Next => (),
                         }
                     }
@@ -95,45 +133,63 @@ impl AmplifierSystem {
         }
 
         // Pop last output
-        last_output
+        Ok((last_output, amp_outputs))
+    }
+
+    /// Run every phase sequence permutation.
+    /// Output (phase sequence, signal) pairs, sorted by descending signal
+    pub fn all_thruster_signals(&self, interpreter: &mut Interpreter) -> Vec<(String, i64)> {
+        let mut signals: Vec<(String, i64)> = (0..AMP_COUNT)
+            .permutations(AMP_COUNT as usize)
+            .map(|permutation| {
+                let phase_sequence: String = permutation.iter().map(|x| x.to_string()).join(",");
+                let output = self
+                    .run_phase_sequence(interpreter, &phase_sequence)
+                    .unwrap();
+                (phase_sequence, output)
+            })
+            .collect();
+
+        // On ties, lexicographically smallest phase sequence wins
+        signals.sort_by(|(sa, a), (sb, b)| b.cmp(a).then_with(|| sa.cmp(sb)));
+        signals
     }
 
-    /// Find max thruster signal
+    /// Build a phase sequence from a permutation of amplifier indices
+    fn phase_sequence_from(permutation: &[usize], offset: i64) -> String {
+        permutation
+            .iter()
+            .map(|&x| (x as i64 + offset).to_string())
+            .join(",")
+    }
+
+    /// Find max thruster signal.
+    /// The parsed program is reset in place for each amp run, and
+    /// permutations are used directly instead of phase sequence strings
     pub fn find_max_thruster_signal(&self, interpreter: &mut Interpreter) -> (i64, String) {
-        let permutations: Vec<_> = (0..5).permutations(5).collect();
-        let mut max_value = 0;
-        let mut max_permutation = String::new();
-
-        for permutation in permutations {
-            let phase_sequence: String = permutation.iter().map(|x| x.to_string()).join(",");
-            let output = self.run_phase_sequence(interpreter, &phase_sequence);
-            if output > max_value {
-                max_value = output;
-                max_permutation = phase_sequence;
-            }
-        }
+        let (permutation, max_value) = argmax_permutation(AMP_COUNT as usize, |permutation| {
+            permutation.iter().fold(0, |output, &phase| {
+                self.run_phase(interpreter, phase as i64, output)
+            })
+        });
 
-        (max_value, max_permutation)
+        (max_value, Self::phase_sequence_from(&permutation, 0))
     }
 
     pub fn find_max_feedback_thruster_signal(
         &self,
         interpreter: &mut Interpreter,
     ) -> (i64, String) {
-        let permutations: Vec<_> = (5..10).permutations(5).collect();
-        let mut max_value = 0;
-        let mut max_permutation = String::new();
-
-        for permutation in permutations {
-            let phase_sequence: String = permutation.iter().map(|x| x.to_string()).join(",");
-            let output = self.run_feedback_phase_sequence(interpreter, &phase_sequence);
-            if output > max_value {
-                max_value = output;
-                max_permutation = phase_sequence;
-            }
-        }
-
-        (max_value, max_permutation)
+        let (permutation, max_value) = argmax_permutation(AMP_COUNT as usize, |permutation| {
+            let phase_sequence = Self::phase_sequence_from(permutation, AMP_COUNT);
+            self.run_feedback_phase_sequence(interpreter, &phase_sequence)
+                .unwrap()
+        });
+
+        (
+            max_value,
+            Self::phase_sequence_from(&permutation, AMP_COUNT),
+        )
     }
 }
 
@@ -155,11 +211,11 @@ fn main() {
     let input_txt = include_str!("../input.txt");
 
     println!("[Part 1]");
-    let r = part1(&input_txt);
+    let r = part1(input_txt);
     println!("Result: {}", r);
 
     println!("[Part 2]");
-    let r = part2(&input_txt);
+    let r = part2(input_txt);
     println!("Result: {}", r);
 }
 
@@ -175,7 +231,7 @@ mod tests {
             system.find_max_thruster_signal(&mut interpreter)
         }
 
-        fn run_phase_sequence(input_txt: &str, seq: &str) -> i64 {
+        fn run_phase_sequence(input_txt: &str, seq: &str) -> Result<i64, String> {
             let mut interpreter = Interpreter::new(input_txt);
             let system = AmplifierSystem::new();
             system.run_phase_sequence(&mut interpreter, seq)
@@ -186,8 +242,20 @@ mod tests {
                 "3,15,3,16,1002,16,10,16,1,16,15,15,4,15,99,0,0",
                 "4,3,2,1,0"
             ),
-            43210
+            Ok(43210)
+        );
+        assert_eq!(
+            run_phase_sequence(
+                "3,15,3,16,1002,16,10,16,1,16,15,15,4,15,99,0,0",
+                "0,0,1,2,3"
+            ),
+            Err("invalid phase sequence: 0,0,1,2,3".to_owned())
         );
+        assert!(run_phase_sequence(
+            "3,15,3,16,1002,16,10,16,1,16,15,15,4,15,99,0,0",
+            "5,6,7,8,9"
+        )
+        .is_err());
 
         assert_eq!(
             run_with_code("3,15,3,16,1002,16,10,16,1,16,15,15,4,15,99,0,0"),
@@ -211,6 +279,66 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_all_thruster_signals() {
+        let mut interpreter = Interpreter::new("3,15,3,16,1002,16,10,16,1,16,15,15,4,15,99,0,0");
+        let system = AmplifierSystem::new();
+        let signals = system.all_thruster_signals(&mut interpreter);
+
+        assert_eq!(signals.len(), 120);
+        assert!(signals.windows(2).all(|w| w[0].1 >= w[1].1));
+
+        let (max_value, max_permutation) = system.find_max_thruster_signal(&mut interpreter);
+        assert_eq!(signals[0], (max_permutation, max_value));
+    }
+
+    #[test]
+    fn test_optimized_search() {
+        let input_txt = include_str!("../input.txt");
+        let codes = [
+            "3,15,3,16,1002,16,10,16,1,16,15,15,4,15,99,0,0",
+            "3,31,3,32,1002,32,10,32,1001,31,-2,31,1007,31,0,33,1002,33,\
+             7,33,1,33,31,31,1,32,31,31,4,31,99,0,0,0",
+            input_txt,
+        ];
+        let system = AmplifierSystem::new();
+
+        for code in codes.iter() {
+            // Each sequence run from scratch
+            let mut interpreter = Interpreter::new(code);
+            let (sequence, signal) = system
+                .all_thruster_signals(&mut interpreter)
+                .into_iter()
+                .next()
+                .unwrap();
+
+            let mut interpreter = Interpreter::new(code);
+            assert_eq!(
+                system.find_max_thruster_signal(&mut interpreter),
+                (signal, sequence)
+            );
+        }
+    }
+
+    #[test]
+    fn test_tie_break() {
+        // Output phase + input: every phase sequence gives the same signal
+        let code = "3,11,3,12,1,11,12,11,4,11,99,0,0";
+        let system = AmplifierSystem::new();
+
+        let mut interpreter = Interpreter::new(code);
+        assert_eq!(
+            system.find_max_thruster_signal(&mut interpreter),
+            (10, "0,1,2,3,4".to_owned())
+        );
+
+        let mut interpreter = Interpreter::new(code);
+        assert_eq!(
+            system.find_max_feedback_thruster_signal(&mut interpreter),
+            (35, "5,6,7,8,9".to_owned())
+        );
+    }
+
     #[test]
     fn test_feedback_amplifiers() {
         fn run_with_code(input_txt: &str) -> (i64, String) {
@@ -219,7 +347,7 @@ mod tests {
             system.find_max_feedback_thruster_signal(&mut interpreter)
         }
 
-        fn run_feedback_phase_sequence(input_txt: &str, seq: &str) -> i64 {
+        fn run_feedback_phase_sequence(input_txt: &str, seq: &str) -> Result<i64, String> {
             let mut interpreter = Interpreter::new(input_txt);
             let system = AmplifierSystem::new();
             system.run_feedback_phase_sequence(&mut interpreter, seq)
@@ -231,7 +359,15 @@ mod tests {
                  27,4,27,1001,28,-1,28,1005,28,6,99,0,0,5",
                 "9,8,7,6,5"
             ),
-            139629729
+            Ok(139629729)
+        );
+        assert_eq!(
+            run_feedback_phase_sequence(
+                "3,26,1001,26,-4,26,3,27,1002,27,2,27,1,27,26,\
+                 27,4,27,1001,28,-1,28,1005,28,6,99,0,0,5",
+                "9,8,7,6"
+            ),
+            Err("invalid phase sequence: 9,8,7,6".to_owned())
         );
         assert_eq!(
             run_with_code(
@@ -251,10 +387,27 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_feedback_detailed() {
+        let mut interpreter = Interpreter::new(
+            "3,26,1001,26,-4,26,3,27,1002,27,2,27,1,27,26,\
+             27,4,27,1001,28,-1,28,1005,28,6,99,0,0,5",
+        );
+        let system = AmplifierSystem::new();
+        let (signal, outputs) = system
+            .run_feedback_detailed(&mut interpreter, "9,8,7,6,5")
+            .unwrap();
+
+        assert_eq!(signal, 139629729);
+        assert_eq!(outputs.len(), 5);
+        assert_eq!(outputs[4], signal);
+        assert!(outputs.windows(2).all(|w| w[0] < w[1]));
+    }
+
     #[test]
     fn test_results() {
         let input_txt = include_str!("../input.txt");
-        assert_eq!(part1(&input_txt), 437860);
-        assert_eq!(part2(&input_txt), 49810599);
+        assert_eq!(part1(input_txt), 437860);
+        assert_eq!(part2(input_txt), 49810599);
     }
 }
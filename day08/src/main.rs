@@ -1,4 +1,5 @@
 use colored::Colorize;
+use common::io::trimmed;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum SpaceColor {
@@ -97,6 +98,7 @@ pub struct SpaceImage {
 
 impl SpaceImage {
     pub fn from_str(input_str: &str, width: usize, height: usize) -> Self {
+        let input_str = trimmed(input_str);
         let layer_size = width * height;
         let layer_count = input_str.len() / layer_size;
         let mut layers = vec![];
@@ -120,7 +122,7 @@ impl SpaceImage {
             None
         } else {
             let mut selected_layer = &self.layers[0];
-            let mut least_color = usize::max_value();
+            let mut least_color = usize::MAX;
             for layer in &self.layers[1..] {
                 let value = layer.count_color(color);
                 if value < least_color {
@@ -133,15 +135,37 @@ impl SpaceImage {
         }
     }
 
+    /// Find index of the first layer with a non-transparent color at idx
+    pub fn blend_layer_at_idx(&self, idx: usize) -> Option<usize> {
+        self.layers
+            .iter()
+            .position(|layer| layer.get_color_at_idx(idx) != SpaceColor::Transparent)
+    }
+
     pub fn blend_color_at_idx(&self, idx: usize) -> SpaceColor {
-        for layer in &self.layers {
-            let color = layer.get_color_at_idx(idx);
-            if color != SpaceColor::Transparent {
-                return color;
-            }
-        }
+        self.blend_layer_at_idx(idx)
+            .map(|layer_idx| self.layers[layer_idx].get_color_at_idx(idx))
+            .unwrap_or(SpaceColor::Transparent)
+    }
+
+    /// Count pixels transparent in every layer
+    pub fn transparent_count(&self) -> usize {
+        (0..self.width * self.height)
+            .filter(|&idx| self.blend_color_at_idx(idx) == SpaceColor::Transparent)
+            .count()
+    }
 
-        SpaceColor::Transparent
+    /// Check if every pixel is covered by a non-transparent layer
+    pub fn is_opaque(&self) -> bool {
+        self.transparent_count() == 0
+    }
+
+    /// List `(pixel_index, layer_index)` of the layer providing each pixel color.
+    /// Layer index is `None` for pixels transparent in every layer
+    pub fn describe_blend(&self) -> Vec<(usize, Option<usize>)> {
+        (0..self.width * self.height)
+            .map(|idx| (idx, self.blend_layer_at_idx(idx)))
+            .collect()
     }
 
     pub fn flatten_image(&self) -> SpaceImageLayer {
@@ -158,6 +182,11 @@ impl SpaceImage {
     pub fn get_layers(&self) -> &[SpaceImageLayer] {
         &self.layers
     }
+
+    /// Draw each layer, from top to bottom
+    pub fn render_layers(&self) -> Vec<String> {
+        self.layers.iter().map(SpaceImageLayer::draw).collect()
+    }
 }
 
 fn part1(input_txt: &str) -> usize {
@@ -178,11 +207,11 @@ fn part2(input_txt: &str) -> String {
 fn main() {
     let input_txt = include_str!("../input.txt");
     println!("[Part 1]");
-    let r = part1(&input_txt);
+    let r = part1(input_txt);
     println!("Result: {}", r);
 
     println!("[Part 2]");
-    let r = part2(&input_txt);
+    let r = part2(input_txt);
     println!("Result: {}", r);
 }
 
@@ -205,11 +234,47 @@ mod tests {
         assert_eq!(layer.get_as_str(), "0110");
     }
 
+    #[test]
+    fn test_opaque() {
+        let image = SpaceImage::from_str("0222112222120000", 2, 2);
+        assert_eq!(image.transparent_count(), 0);
+        assert!(image.is_opaque());
+
+        let image = SpaceImage::from_str("2222", 2, 2);
+        assert_eq!(image.transparent_count(), 4);
+        assert!(!image.is_opaque());
+
+        let image = SpaceImage::from_str("2202", 2, 1);
+        assert_eq!(image.transparent_count(), 1);
+        assert!(!image.is_opaque());
+    }
+
+    #[test]
+    fn test_describe_blend() {
+        let image = SpaceImage::from_str("0222112222120000", 2, 2);
+        assert_eq!(
+            image.describe_blend(),
+            vec![(0, Some(0)), (1, Some(1)), (2, Some(2)), (3, Some(3))]
+        );
+
+        let image = SpaceImage::from_str("2202", 2, 1);
+        assert_eq!(image.describe_blend(), vec![(0, Some(1)), (1, None)]);
+    }
+
+    #[test]
+    fn test_render_layers() {
+        let image = SpaceImage::from_str("0222112222120000", 2, 2);
+        let frames = image.render_layers();
+        assert_eq!(frames.len(), image.get_layers().len());
+        assert_eq!(frames[0], image.get_layers()[0].draw());
+        assert_eq!(frames[3], image.get_layers()[3].draw());
+    }
+
     #[test]
     fn test_results() {
         let input_txt = include_str!("../input.txt");
-        assert_eq!(part1(&input_txt), 1690);
-        assert_eq!(part2(&input_txt), "111101110011110100101110000010100100001010010100100010010010001001001011100010001110001000100101001010000100001000010010100101111010000111100110011100");
+        assert_eq!(part1(input_txt), 1690);
+        assert_eq!(part2(input_txt), "111101110011110100101110000010100100001010010100100010010010001001001011100010001110001000100101001010000100001000010010100101111010000111100110011100");
         // Text: ZPZUB
     }
 }
@@ -20,11 +20,11 @@ fn main() {
     let input_txt = include_str!("../input.txt");
 
     println!("[Part 1]");
-    let r = part1(&input_txt);
+    let r = part1(input_txt);
     println!("Result: {}", r);
 
     println!("[Part 2]");
-    let r = part2(&input_txt);
+    let r = part2(input_txt);
     println!("Result: {}", r);
 }
 
@@ -35,7 +35,7 @@ mod tests {
     #[test]
     fn test_results() {
         let input_txt = include_str!("../input.txt");
-        assert_eq!(part1(&input_txt), 3_765_554_916);
-        assert_eq!(part2(&input_txt), 76_642);
+        assert_eq!(part1(input_txt), 3_765_554_916);
+        assert_eq!(part2(input_txt), 76_642);
     }
 }
@@ -1,5 +1,10 @@
 use colored::Colorize;
+use common::io::split_lines;
 use itertools::Itertools;
+use num::integer::Integer;
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::f32::consts::PI;
 use std::{thread, time};
 
 #[derive(Debug, Clone)]
@@ -12,13 +17,31 @@ pub struct AsteroidMap {
 
 impl AsteroidMap {
     pub fn from_input(input_txt: &str) -> Self {
-        let lines: Vec<_> = input_txt.split('\n').collect();
-        let width = lines[0].chars().count();
+        Self::try_from_input(input_txt).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Parse map, padding lines shorter than the first one with `.`.
+    /// Lines longer than the first one are rejected
+    pub fn try_from_input(input_txt: &str) -> Result<Self, String> {
+        let lines = split_lines(input_txt);
+        let width = lines
+            .first()
+            .ok_or_else(|| "empty map".to_owned())?
+            .chars()
+            .count();
         let height = lines.len();
         let mut asteroid_locations = vec![];
         let mut data = vec![];
 
         for (y, line) in lines.iter().enumerate() {
+            let line_width = line.chars().count();
+            if line_width > width {
+                return Err(format!(
+                    "line {} is too long: {} > {}",
+                    y, line_width, width
+                ));
+            }
+
             for (x, c) in line.chars().enumerate() {
                 if c == '#' {
                     asteroid_locations.push((x, y));
@@ -26,21 +49,39 @@ impl AsteroidMap {
 
                 data.push(c);
             }
+
+            data.extend(std::iter::repeat_n('.', width - line_width));
         }
 
-        Self {
+        Ok(Self {
             data,
             width,
             height,
             asteroid_locations,
-        }
+        })
+    }
+
+    /// Check if position is inside the map
+    pub fn in_bounds(&self, x: usize, y: usize) -> bool {
+        x < self.width && y < self.height
+    }
+
+    /// Get in-bounds positions around (x, y), diagonals included
+    pub fn neighbors(&self, x: usize, y: usize) -> impl Iterator<Item = (usize, usize)> + '_ {
+        (-1i64..=1)
+            .flat_map(|oy| (-1i64..=1).map(move |ox| (ox, oy)))
+            .filter(|&offset| offset != (0, 0))
+            .map(move |(ox, oy)| (x as i64 + ox, y as i64 + oy))
+            .filter(|&(nx, ny)| nx >= 0 && ny >= 0)
+            .map(|(nx, ny)| (nx as usize, ny as usize))
+            .filter(move |&(nx, ny)| self.in_bounds(nx, ny))
     }
 
     pub fn get_char(&self, x: usize, y: usize) -> char {
-        if x >= self.width || y >= self.height {
-            ' '
-        } else {
+        if self.in_bounds(x, y) {
             self.data[x + y * self.width]
+        } else {
+            ' '
         }
     }
 
@@ -54,14 +95,14 @@ impl AsteroidMap {
         -(x1 - x2).atan2(y1 - y2) * 1000.0
     }
 
-    /// Compute
+    /// Compute manhattan distance between (x1, y1) and (x2, y2)
     pub fn compute_distance(&self, x1: usize, y1: usize, x2: usize, y2: usize) -> usize {
-        ((x1 as i32 - x2 as i32).abs() + (y1 as i32 - y2 as i32).abs()) as usize
+        ((x1 as i64 - x2 as i64).abs() + (y1 as i64 - y2 as i64).abs()) as usize
     }
 
     /// Scan asteroids at point
     pub fn scan_point(&self, x: usize, y: usize) -> usize {
-        if self.get_char(x, y) == '.' {
+        if !self.in_bounds(x, y) || self.get_char(x, y) == '.' {
             return 0;
         }
 
@@ -103,7 +144,7 @@ impl AsteroidMap {
     }
 
     pub fn sort_asteroids_from_point(&self, x: usize, y: usize) -> Vec<(usize, usize)> {
-        if self.get_char(x, y) == '.' {
+        if !self.in_bounds(x, y) || self.get_char(x, y) == '.' {
             return vec![];
         }
 
@@ -135,7 +176,7 @@ impl AsteroidMap {
         let mut destroyed = vec![];
         loop {
             let mut new_sorted_asteroids = vec![];
-            let mut prev_angle = i32::max_value();
+            let mut prev_angle = i32::MAX;
             for ((x, y), ang, dist) in &sorted_asteroids {
                 if prev_angle != *ang {
                     destroyed.push((*x, *y));
@@ -156,30 +197,129 @@ impl AsteroidMap {
         destroyed
     }
 
+    /// Get vaporization order from a station, starting at `start_angle`
+    /// (in radians, 0 is up) and sweeping clockwise or counter-clockwise.
+    /// Asteroids are grouped by exact direction, using the gcd of the offsets
+    pub fn vaporization_order(
+        &self,
+        station: (usize, usize),
+        start_angle: f32,
+        clockwise: bool,
+    ) -> Vec<(usize, usize)> {
+        let mut lines: HashMap<(i64, i64), Vec<(usize, usize)>> = HashMap::new();
+
+        for &(ax, ay) in &self.asteroid_locations {
+            if (ax, ay) == station {
+                continue;
+            }
+
+            lines
+                .entry(Self::reduced_direction(station, (ax, ay)))
+                .or_default()
+                .push((ax, ay));
+        }
+
+        let mut sweep: Vec<(f32, Vec<(usize, usize)>)> = lines
+            .into_iter()
+            .map(|((dx, dy), mut line)| {
+                // Clockwise angle from up, with y pointing down
+                let angle = (dx as f32).atan2(-dy as f32);
+                let offset = if clockwise {
+                    angle - start_angle
+                } else {
+                    start_angle - angle
+                };
+
+                // Farthest first, to pop the nearest
+                line.sort_by_key(|&(ax, ay)| {
+                    Reverse(self.compute_distance(station.0, station.1, ax, ay))
+                });
+                (offset.rem_euclid(2.0 * PI), line)
+            })
+            .collect();
+        sweep.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+
+        let mut destroyed = vec![];
+        while destroyed.len() < self.asteroid_locations.len() {
+            let count = destroyed.len();
+            for (_, line) in &mut sweep {
+                if let Some(pos) = line.pop() {
+                    destroyed.push(pos);
+                }
+            }
+
+            if destroyed.len() == count {
+                break;
+            }
+        }
+
+        destroyed
+    }
+
+    /// Get direction from station to asteroid, divided by the gcd of the offsets
+    fn reduced_direction(station: (usize, usize), asteroid: (usize, usize)) -> (i64, i64) {
+        let dx = asteroid.0 as i64 - station.0 as i64;
+        let dy = asteroid.1 as i64 - station.1 as i64;
+        let div = dx.gcd(&dy);
+        (dx / div, dy / div)
+    }
+
+    /// Get clockwise angle from up of each other asteroid, in degrees in `[0, 360)`
+    pub fn angles_from(&self, station: (usize, usize)) -> Vec<((usize, usize), f32)> {
+        self.asteroid_locations
+            .iter()
+            .filter(|&&asteroid| asteroid != station)
+            .map(|&asteroid| {
+                let (dx, dy) = Self::reduced_direction(station, asteroid);
+                // Y is pointing down
+                let angle = (dx as f32).atan2(-dy as f32).to_degrees();
+                (asteroid, angle.rem_euclid(360.0))
+            })
+            .collect()
+    }
+
+    /// Count visible asteroids from each asteroid
+    pub fn visibility_counts(&self) -> HashMap<(usize, usize), usize> {
+        self.asteroid_locations
+            .iter()
+            .map(|&(x, y)| ((x, y), self.scan_point(x, y)))
+            .collect()
+    }
+
     pub fn better_position(&self) -> ((usize, usize), usize) {
+        let counts = self.visibility_counts();
         let mut better_pos = (0, 0);
         let mut better_count = 0;
 
-        for (ax, ay) in &self.asteroid_locations {
-            let count = self.scan_point(*ax, *ay);
+        for pos in &self.asteroid_locations {
+            let count = counts[pos];
             if count > better_count {
                 better_count = count;
-                better_pos = (*ax, *ay);
+                better_pos = *pos;
             }
         }
 
         (better_pos, better_count)
     }
 
+    /// Get the `n` best positions by visible asteroid count, best first.
+    /// Ties are ordered by position
+    pub fn top_n_positions(&self, n: usize) -> Vec<((usize, usize), usize)> {
+        let mut counts: Vec<_> = self.visibility_counts().into_iter().collect();
+        counts.sort_by(|(pos1, count1), (pos2, count2)| count2.cmp(count1).then(pos1.cmp(pos2)));
+        counts.truncate(n);
+        counts
+    }
+
     pub fn dump_scan(&self) -> String {
+        let counts = self.visibility_counts();
         let mut output = String::new();
 
         for y in 0..self.height {
             for x in 0..self.width {
-                if self.get_char(x, y) == '.' {
-                    output.push('.');
-                } else {
-                    output.push_str(&self.scan_point(x, y).to_string());
+                match counts.get(&(x, y)) {
+                    Some(count) => output.push_str(&count.to_string()),
+                    None => output.push('.'),
                 }
             }
 
@@ -230,17 +370,18 @@ fn main() {
     let input_txt = include_str!("../input.txt");
 
     println!("[Part 1]");
-    let r = part1(&input_txt);
+    let r = part1(input_txt);
     println!("Result: {}", r);
 
     println!("[Part 2]");
-    let r = part2(&input_txt);
+    let r = part2(input_txt);
     println!("Result: {}", r);
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use common::float_eq;
 
     #[test]
     fn test_small() {
@@ -269,6 +410,153 @@ mod tests {
         assert_eq!(ast_map.better_position(), ((3, 4), 8));
     }
 
+    #[test]
+    fn test_ragged() {
+        let ast_map = AsteroidMap::from_input(
+            "#.#.\n\
+             #\n\
+             ..#",
+        );
+        assert_eq!(
+            ast_map.dump(),
+            "#.#.\n\
+             #...\n\
+             ..#."
+        );
+        assert_eq!(ast_map.get_char(0, 1), '#');
+        assert_eq!(ast_map.get_char(1, 1), '.');
+        assert_eq!(ast_map.get_char(3, 1), '.');
+        assert_eq!(ast_map.get_char(2, 2), '#');
+        assert_eq!(ast_map.visibility_counts().len(), 4);
+
+        assert_eq!(
+            AsteroidMap::try_from_input("#.\n#.#").err(),
+            Some("line 1 is too long: 3 > 2".to_owned())
+        );
+        assert_eq!(
+            AsteroidMap::try_from_input("\n").err(),
+            Some("empty map".to_owned())
+        );
+
+        // Blank interior rows are padded, keeping later coordinates
+        let ast_map = AsteroidMap::from_input(".#\n\n #\n");
+        assert_eq!(ast_map.dump(), ".#\n..\n #");
+        assert_eq!(ast_map.height, 3);
+        assert_eq!(ast_map.get_char(1, 2), '#');
+    }
+
+    #[test]
+    fn test_bounds() {
+        let ast_map = AsteroidMap::from_input(
+            ".#.\n\
+             ###",
+        );
+
+        assert!(ast_map.in_bounds(0, 0));
+        assert!(ast_map.in_bounds(2, 1));
+        assert!(!ast_map.in_bounds(3, 1));
+        assert!(!ast_map.in_bounds(2, 2));
+        assert!(!ast_map.in_bounds(usize::MAX, 0));
+        assert_eq!(ast_map.get_char(3, 0), ' ');
+        assert_eq!(ast_map.scan_point(3, 0), 0);
+        assert!(ast_map.sort_asteroids_from_point(0, 2).is_empty());
+
+        assert_eq!(
+            ast_map.neighbors(0, 0).collect::<Vec<_>>(),
+            vec![(1, 0), (0, 1), (1, 1)]
+        );
+        assert_eq!(ast_map.neighbors(1, 1).count(), 5);
+        assert_eq!(
+            ast_map.compute_distance(0, 0, usize::MAX / 4, 1),
+            usize::MAX / 4 + 1
+        );
+    }
+
+    #[test]
+    fn test_visibility_counts() {
+        let map = ".#..#\n\
+                   .....\n\
+                   #####\n\
+                   ....#\n\
+                   ...##";
+
+        let ast_map = AsteroidMap::from_input(map);
+        let counts = ast_map.visibility_counts();
+        assert_eq!(counts.len(), 10);
+        assert_eq!(counts[&(1, 0)], 7);
+        assert_eq!(
+            counts.values().max().copied(),
+            Some(ast_map.better_position().1)
+        );
+    }
+
+    #[test]
+    fn test_vaporization_order() {
+        let ast_map = AsteroidMap::from_input(
+            ".#.\n\
+             ###\n\
+             .#.",
+        );
+
+        assert_eq!(
+            ast_map.vaporization_order((1, 1), 0.0, true),
+            vec![(1, 0), (2, 1), (1, 2), (0, 1)]
+        );
+        assert_eq!(
+            ast_map.vaporization_order((1, 1), 0.0, false),
+            vec![(1, 0), (0, 1), (1, 2), (2, 1)]
+        );
+        assert_eq!(
+            ast_map.vaporization_order((1, 1), PI, true),
+            vec![(1, 2), (0, 1), (1, 0), (2, 1)]
+        );
+
+        // Nearest asteroids first, one per rotation
+        let ast_map = AsteroidMap::from_input(
+            "#.#\n\
+             #..\n\
+             #..",
+        );
+        assert_eq!(
+            ast_map.vaporization_order((0, 2), 0.0, true),
+            vec![(0, 1), (2, 0), (0, 0)]
+        );
+    }
+
+    #[test]
+    fn test_angles_from() {
+        let ast_map = AsteroidMap::from_input(
+            ".#.\n\
+             ###\n\
+             .##",
+        );
+
+        let angles: HashMap<_, _> = ast_map.angles_from((1, 1)).into_iter().collect();
+        assert_eq!(angles.len(), 5);
+        assert!(float_eq(angles[&(1, 0)], 0.0));
+        assert!(float_eq(angles[&(2, 1)], 90.0));
+        assert!(float_eq(angles[&(2, 2)], 135.0));
+        assert!(float_eq(angles[&(1, 2)], 180.0));
+        assert!(float_eq(angles[&(0, 1)], 270.0));
+        assert!(angles.values().all(|&a| (0.0..360.0).contains(&a)));
+    }
+
+    #[test]
+    fn test_top_n_positions() {
+        let ast_map = AsteroidMap::from_input(
+            ".#..#\n\
+             .....\n\
+             #####\n\
+             ....#\n\
+             ...##",
+        );
+
+        let top = ast_map.top_n_positions(3);
+        assert_eq!(top[0], ast_map.better_position());
+        assert_eq!(top, vec![((3, 4), 8), ((1, 0), 7), ((1, 2), 7)]);
+        assert_eq!(ast_map.top_n_positions(100).len(), 10);
+    }
+
     #[test]
     fn test_medium() {
         assert_eq!(
@@ -356,7 +644,14 @@ mod tests {
     #[test]
     fn test_results() {
         let input_txt = include_str!("../input.txt");
-        assert_eq!(part1(&input_txt), 329);
-        assert_eq!(part2(&input_txt), 512);
+        assert_eq!(part1(input_txt), 329);
+        assert_eq!(part2(input_txt), 512);
+
+        let map = AsteroidMap::from_input(input_txt);
+        let ((x, y), _) = map.better_position();
+        assert_eq!(
+            map.vaporization_order((x, y), 0.0, true),
+            map.sort_asteroids_from_point(x, y)
+        );
     }
 }
@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 use common::interpreter::{ExecutionState, Interpreter};
 
@@ -72,15 +72,29 @@ impl Direction {
     }
 }
 
+/// Painting run statistics
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RunStats {
+    pub steps: usize,
+    pub unique_panels: usize,
+    pub repaints: usize,
+}
+
 #[derive(Debug)]
 pub struct DrawSim {
     tiles: HashMap<(i32, i32), Color>,
+    initial_panels: HashMap<(i32, i32), Color>,
     robot_position: (i32, i32),
     interpreter: Interpreter,
 }
 
 impl DrawSim {
     pub fn new(code: &str) -> Self {
+        Self::with_initial_panels(code, HashMap::new())
+    }
+
+    /// Create simulation on already painted panels
+    pub fn with_initial_panels(code: &str, panels: HashMap<(i32, i32), Color>) -> Self {
         let robot_position = (0, 0);
         let interpreter = Interpreter::new(code);
         let tiles = HashMap::new();
@@ -88,6 +102,7 @@ impl DrawSim {
         Self {
             robot_position,
             tiles,
+            initial_panels: panels,
             interpreter,
         }
     }
@@ -101,7 +116,11 @@ impl DrawSim {
     }
 
     pub fn get_color_at_position(&self, pos: (i32, i32)) -> Color {
-        self.tiles.get(&pos).copied().unwrap_or(Color::Black)
+        self.tiles
+            .get(&pos)
+            .or_else(|| self.initial_panels.get(&pos))
+            .copied()
+            .unwrap_or(Color::Black)
     }
 
     pub fn move_robot(&mut self, direction: Direction) {
@@ -111,8 +130,21 @@ impl DrawSim {
     }
 
     pub fn run(&mut self, base_color: Color) {
+        self.run_recorded(base_color);
+    }
+
+    /// Run and record each step painted position, painted color and new direction.
+    /// `base_color` is the starting panel color, unless that panel is preloaded
+    pub fn run_recorded(&mut self, base_color: Color) -> Vec<((i32, i32), Color, Direction)> {
         let mut direction = Direction::Up;
-        let mut color = base_color;
+        let mut color = if self.tiles.contains_key(&self.robot_position)
+            || self.initial_panels.contains_key(&self.robot_position)
+        {
+            self.get_color_at_robot()
+        } else {
+            base_color
+        };
+        let mut history = vec![];
 
         loop {
             let (new_color, new_direction, state) = self.step(color, direction);
@@ -121,18 +153,60 @@ impl DrawSim {
             }
 
             // Paint
+            history.push((self.robot_position, new_color, new_direction));
             self.paint_position(new_color);
             self.move_robot(new_direction);
             direction = new_direction;
             color = self.get_color_at_robot();
         }
+
+        history
+    }
+
+    /// Run and count steps, panels painted during the run and repainted panels
+    pub fn run_with_stats(&mut self, base_color: Color) -> RunStats {
+        let history = self.run_recorded(base_color);
+        let mut painted = HashSet::new();
+        let mut repaints = 0;
+
+        for (position, _, _) in &history {
+            if !painted.insert(*position) {
+                repaints += 1;
+            }
+        }
+
+        RunStats {
+            steps: history.len(),
+            unique_panels: painted.len(),
+            repaints,
+        }
+    }
+
+    /// Count panels painted at least once by the robot
+    pub fn count_painted(&self) -> usize {
+        self.tiles.len()
+    }
+
+    /// Count panels currently white
+    pub fn count_white(&self) -> usize {
+        self.tiles.values().filter(|&c| *c == Color::White).count()
+    }
+
+    /// Count painted panels by current color, as `(black, white)`
+    pub fn color_histogram(&self) -> (usize, usize) {
+        self.tiles
+            .values()
+            .fold((0, 0), |(black, white), color| match color {
+                Color::Black => (black + 1, white),
+                Color::White => (black, white + 1),
+            })
     }
 
     pub fn get_rect(&self) -> (i32, i32, i32, i32) {
-        let mut x_min = i32::max_value();
-        let mut x_max = i32::min_value();
-        let mut y_min = i32::max_value();
-        let mut y_max = i32::min_value();
+        let mut x_min = i32::MAX;
+        let mut x_max = i32::MIN;
+        let mut y_min = i32::MAX;
+        let mut y_max = i32::MIN;
 
         for (x, y) in self.tiles.keys().copied() {
             if x < x_min {
@@ -152,6 +226,24 @@ impl DrawSim {
         (x_min, y_min, x_max - x_min, y_max - y_min + 1)
     }
 
+    /// Get painted tiles as a dense row-major grid covering the bounding rect.
+    /// Unvisited cells are black.
+    pub fn to_grid(&self) -> Vec<Vec<Color>> {
+        if self.tiles.is_empty() {
+            return vec![];
+        }
+
+        let (ox, oy, w, h) = self.get_rect();
+        // `get_rect` width does not include the last column
+        (oy..oy + h)
+            .map(|y| {
+                (ox..=ox + w)
+                    .map(|x| self.get_color_at_position((x, y)))
+                    .collect()
+            })
+            .collect()
+    }
+
     pub fn draw(&self) -> String {
         let mut string = String::new();
         let (ox, oy, w, h) = self.get_rect();
@@ -203,7 +295,7 @@ impl DrawSim {
 fn part1(input_txt: &str) -> usize {
     let mut sim = DrawSim::new(input_txt);
     sim.run(Color::Black);
-    sim.tiles.keys().count()
+    sim.count_painted()
 }
 
 fn part2(input_txt: &str) -> String {
@@ -216,11 +308,11 @@ fn main() {
     let input_txt = include_str!("../input.txt");
 
     println!("[Part 1]");
-    let r = part1(&input_txt);
+    let r = part1(input_txt);
     println!("Result: {}", r);
 
     println!("[Part 2]");
-    let r = part2(&input_txt);
+    let r = part2(input_txt);
     println!("Result:\n{}", r);
 }
 
@@ -231,15 +323,120 @@ mod tests {
     #[test]
     fn test_creation() {
         let input_txt = include_str!("../input.txt");
-        let map = DrawSim::new(&input_txt);
+        let map = DrawSim::new(input_txt);
         assert_eq!(map.robot_position, (0, 0));
     }
 
+    #[test]
+    fn test_counts() {
+        // Paint white, turn left, then paint black, turn left
+        let mut sim = DrawSim::new("3,100,104,1,104,0,3,100,104,0,104,0,3,100,99");
+        sim.run(Color::Black);
+        assert_eq!(sim.count_painted(), 2);
+        assert_eq!(sim.count_white(), 1);
+
+        let (black, white) = sim.color_histogram();
+        assert_eq!((black, white), (1, 1));
+        assert_eq!(black + white, sim.count_painted());
+    }
+
+    #[test]
+    fn test_run_recorded() {
+        let mut sim = DrawSim::new("3,100,104,1,104,0,3,100,104,0,104,0,3,100,99");
+        assert_eq!(
+            sim.run_recorded(Color::Black),
+            vec![
+                ((0, 0), Color::White, Direction::Left),
+                ((-1, 0), Color::Black, Direction::Down),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_run_with_stats() {
+        // Paint white, alternately turn left and right
+        let mut sim = DrawSim::new("3,100,104,1,104,0,3,100,104,1,104,1,3,100,99");
+        assert_eq!(
+            sim.run_with_stats(Color::Black),
+            RunStats {
+                steps: 2,
+                unique_panels: 2,
+                repaints: 0
+            }
+        );
+
+        // Paint white and turn right five times, back to origin
+        let code = "3,100,104,1,104,1,".repeat(5) + "3,100,99";
+        let mut sim = DrawSim::new(&code);
+        assert_eq!(
+            sim.run_with_stats(Color::Black),
+            RunStats {
+                steps: 5,
+                unique_panels: 4,
+                repaints: 1
+            }
+        );
+    }
+
+    #[test]
+    fn test_with_initial_panels() {
+        // Paint the read color, then turn left
+        let code = "3,100,4,100,104,0,3,100,4,100,104,0,3,100,99";
+        let mut panels = HashMap::new();
+        panels.insert((-1, 0), Color::White);
+
+        let mut sim = DrawSim::with_initial_panels(code, panels);
+        assert_eq!(sim.get_color_at_position((-1, 0)), Color::White);
+        assert_eq!(
+            sim.run_recorded(Color::Black),
+            vec![
+                ((0, 0), Color::Black, Direction::Left),
+                ((-1, 0), Color::White, Direction::Down),
+            ]
+        );
+
+        let mut sim = DrawSim::new(code);
+        assert_eq!(sim.run_recorded(Color::Black)[1].1, Color::Black);
+
+        // Preloaded starting panel
+        let mut panels = HashMap::new();
+        panels.insert((0, 0), Color::White);
+        let mut sim = DrawSim::with_initial_panels(code, panels);
+        assert_eq!(sim.run_recorded(Color::Black)[0].1, Color::White);
+
+        // Preloaded panels the robot never visits are not counted
+        let mut panels = HashMap::new();
+        panels.insert((5, 5), Color::White);
+        let mut sim = DrawSim::with_initial_panels(code, panels);
+        assert_eq!(sim.count_painted(), 0);
+        sim.run(Color::Black);
+        assert_eq!(sim.count_painted(), 2);
+        assert_eq!(sim.count_white(), 0);
+        assert_eq!(sim.color_histogram(), (2, 0));
+    }
+
+    #[test]
+    fn test_to_grid() {
+        let mut sim = DrawSim::new("99");
+        assert!(sim.to_grid().is_empty());
+
+        sim.tiles.insert((-1, 0), Color::White);
+        sim.tiles.insert((1, 1), Color::White);
+        sim.tiles.insert((0, 1), Color::Black);
+        assert_eq!(
+            sim.to_grid(),
+            vec![
+                vec![Color::White, Color::Black, Color::Black],
+                vec![Color::Black, Color::Black, Color::White],
+            ]
+        );
+    }
+
     #[test]
     fn test_results() {
         let input_txt = include_str!("../input.txt");
         let part2_result = include_str!("../part2_result.txt");
-        assert_eq!(part1(&input_txt), 2_088);
-        assert_eq!(part2(&input_txt), part2_result);
+        assert_eq!(part1(input_txt), 2_088);
+        assert_eq!(part2(input_txt), part2_result);
     }
 }
@@ -1,8 +1,26 @@
-use itertools::Itertools;
+use common::io::split_lines;
 use num::integer::Integer;
 
 pub type Vector3D = euclid::default::Vector3D<i32>;
 
+fn get_axis(vector: Vector3D, axis: usize) -> i32 {
+    match axis {
+        0 => vector.x,
+        1 => vector.y,
+        2 => vector.z,
+        _ => panic!("wrong axis: {}", axis),
+    }
+}
+
+fn get_axis_mut(vector: &mut Vector3D, axis: usize) -> &mut i32 {
+    match axis {
+        0 => &mut vector.x,
+        1 => &mut vector.y,
+        2 => &mut vector.z,
+        _ => panic!("wrong axis: {}", axis),
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct Moon {
     position: Vector3D,
@@ -10,47 +28,68 @@ pub struct Moon {
 }
 
 impl Moon {
+    /// Parse moon from `<x=1, y=-2, z=3>`.
+    /// Whitespace around coordinates and brackets is ignored
     pub fn from_input(input: &str) -> Self {
-        let coords = &input[1..input.len() - 1]
-            .split(", ")
-            .map(|x| {
-                x.split('=')
-                    .skip(1)
-                    .map(|y| y.parse::<i32>().unwrap())
-                    .next()
-                    .unwrap()
-            })
-            .collect::<Vec<i32>>();
+        Self::try_from_input(input).unwrap_or_else(|e| panic!("{}", e))
+    }
 
-        Self {
-            position: Vector3D::new(coords[0], coords[1], coords[2]),
-            velocity: Vector3D::default(),
+    /// Parse moon from `<x=1, y=-2, z=3>`, requiring each coordinate exactly once
+    pub fn try_from_input(input: &str) -> Result<Self, String> {
+        let mut coords: [Option<i32>; 3] = [None; 3];
+        let entries = input
+            .trim()
+            .trim_start_matches('<')
+            .trim_end_matches('>')
+            .split(',');
+
+        for coord in entries {
+            let entry: Vec<&str> = coord.split('=').map(str::trim).collect();
+            let value = entry
+                .get(1)
+                .and_then(|x| x.parse::<i32>().ok())
+                .ok_or_else(|| format!("invalid coordinate: {:?}", coord))?;
+            let axis = match entry[0] {
+                "x" => 0,
+                "y" => 1,
+                "z" => 2,
+                _ => return Err(format!("invalid coordinate name: {:?}", coord)),
+            };
+
+            if coords[axis].replace(value).is_some() {
+                return Err(format!("duplicate coordinate: {}", entry[0]));
+            }
         }
-    }
 
-    pub fn apply_gravity(&mut self, other_moon: &mut Self) {
-        if self.position.x < other_moon.position.x {
-            self.velocity.x += 1;
-            other_moon.velocity.x -= 1;
-        } else if self.position.x > other_moon.position.x {
-            self.velocity.x -= 1;
-            other_moon.velocity.x += 1;
+        let mut position = Vector3D::default();
+        for (axis, name) in ["x", "y", "z"].iter().enumerate() {
+            let value = coords[axis].ok_or_else(|| format!("missing coordinate: {}", name))?;
+            *get_axis_mut(&mut position, axis) = value;
         }
 
-        if self.position.y < other_moon.position.y {
-            self.velocity.y += 1;
-            other_moon.velocity.y -= 1;
-        } else if self.position.y > other_moon.position.y {
-            self.velocity.y -= 1;
-            other_moon.velocity.y += 1;
+        Ok(Self {
+            position,
+            velocity: Vector3D::default(),
+        })
+    }
+
+    pub fn apply_gravity(&mut self, other_moon: &mut Self) {
+        for axis in 0..3 {
+            self.apply_gravity_axis(other_moon, axis);
         }
+    }
 
-        if self.position.z < other_moon.position.z {
-            self.velocity.z += 1;
-            other_moon.velocity.z -= 1;
-        } else if self.position.z > other_moon.position.z {
-            self.velocity.z -= 1;
-            other_moon.velocity.z += 1;
+    /// Apply gravity on a single axis (0: x, 1: y, 2: z)
+    pub fn apply_gravity_axis(&mut self, other_moon: &mut Self, axis: usize) {
+        let position = get_axis(self.position, axis);
+        let other_position = get_axis(other_moon.position, axis);
+
+        if position < other_position {
+            *get_axis_mut(&mut self.velocity, axis) += 1;
+            *get_axis_mut(&mut other_moon.velocity, axis) -= 1;
+        } else if position > other_position {
+            *get_axis_mut(&mut self.velocity, axis) -= 1;
+            *get_axis_mut(&mut other_moon.velocity, axis) += 1;
         }
     }
 
@@ -58,6 +97,11 @@ impl Moon {
         self.position += self.velocity;
     }
 
+    /// Integrate velocity on a single axis (0: x, 1: y, 2: z)
+    pub fn integrate_velocity_axis(&mut self, axis: usize) {
+        *get_axis_mut(&mut self.position, axis) += get_axis(self.velocity, axis);
+    }
+
     pub fn compute_total_energy(&self) -> usize {
         let pot = self.position.x.abs() + self.position.y.abs() + self.position.z.abs();
         let kin = self.velocity.x.abs() + self.velocity.y.abs() + self.velocity.z.abs();
@@ -65,7 +109,7 @@ impl Moon {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct MoonSim {
     moons: Vec<Moon>,
 }
@@ -73,24 +117,40 @@ pub struct MoonSim {
 impl MoonSim {
     pub fn from_input(input: &str) -> Self {
         Self {
-            moons: input.split('\n').map(Moon::from_input).collect(),
+            moons: split_lines(input)
+                .into_iter()
+                .map(Moon::from_input)
+                .collect(),
         }
     }
 
     pub fn step(&mut self) {
-        let combinations: Vec<Vec<usize>> = (0..self.moons.len()).combinations(2).collect();
-        for combination in combinations {
-            let mut moon1 = self.moons[combination[0]].clone();
-            let mut moon2 = self.moons[combination[1]].clone();
+        // Apply gravity on each pair, splitting borrows to avoid clones
+        for idx in 0..self.moons.len() {
+            let (left, right) = self.moons.split_at_mut(idx + 1);
+            let moon1 = &mut left[idx];
+            for moon2 in right.iter_mut() {
+                moon1.apply_gravity(moon2);
+            }
+        }
 
-            moon1.apply_gravity(&mut moon2);
+        for moon in &mut self.moons {
+            moon.integrate_velocity();
+        }
+    }
 
-            self.moons[combination[0]] = moon1;
-            self.moons[combination[1]] = moon2;
+    /// Step a single axis (0: x, 1: y, 2: z), leaving the others unchanged
+    pub fn step_axis(&mut self, axis: usize) {
+        for idx in 0..self.moons.len() {
+            let (left, right) = self.moons.split_at_mut(idx + 1);
+            let moon1 = &mut left[idx];
+            for moon2 in right.iter_mut() {
+                moon1.apply_gravity_axis(moon2, axis);
+            }
         }
 
         for moon in &mut self.moons {
-            moon.integrate_velocity();
+            moon.integrate_velocity_axis(axis);
         }
     }
 
@@ -100,79 +160,91 @@ impl MoonSim {
         }
     }
 
+    /// Step `steps` times, recording total energy after each step
+    pub fn energy_trace(&mut self, steps: usize) -> Vec<usize> {
+        (0..steps)
+            .map(|_| {
+                self.step();
+                self.compute_total_energy()
+            })
+            .collect()
+    }
+
     pub fn get_x_positions(&self) -> Vec<i32> {
-        self.moons.iter().map(|m| m.position.x).collect()
+        self.get_axis_positions(0)
     }
 
     pub fn get_y_positions(&self) -> Vec<i32> {
-        self.moons.iter().map(|m| m.position.y).collect()
+        self.get_axis_positions(1)
     }
 
     pub fn get_z_positions(&self) -> Vec<i32> {
-        self.moons.iter().map(|m| m.position.z).collect()
+        self.get_axis_positions(2)
     }
 
     pub fn get_x_velocities(&self) -> Vec<i32> {
-        self.moons.iter().map(|m| m.velocity.x).collect()
+        self.get_axis_velocities(0)
     }
 
     pub fn get_y_velocities(&self) -> Vec<i32> {
-        self.moons.iter().map(|m| m.velocity.y).collect()
+        self.get_axis_velocities(1)
     }
 
     pub fn get_z_velocities(&self) -> Vec<i32> {
-        self.moons.iter().map(|m| m.velocity.z).collect()
+        self.get_axis_velocities(2)
     }
 
-    /// Find repeating cycles on independent coordinates,
-    /// then compute LCM between the 3
-    pub fn find_cycle(&mut self) -> u64 {
-        let init_pos_x: Vec<i32> = self.moons.iter().map(|m| m.position.x).collect();
-        let init_pos_y: Vec<i32> = self.moons.iter().map(|m| m.position.y).collect();
-        let init_pos_z: Vec<i32> = self.moons.iter().map(|m| m.position.z).collect();
-        let init_vel = vec![0, 0, 0, 0];
+    pub fn get_axis_positions(&self, axis: usize) -> Vec<i32> {
+        self.moons
+            .iter()
+            .map(|m| get_axis(m.position, axis))
+            .collect()
+    }
+
+    pub fn get_axis_velocities(&self, axis: usize) -> Vec<i32> {
+        self.moons
+            .iter()
+            .map(|m| get_axis(m.velocity, axis))
+            .collect()
+    }
 
-        let mut repeat_x: u64 = 0;
-        let mut repeat_y: u64 = 0;
-        let mut repeat_z: u64 = 0;
+    /// Find repeating cycle on a single axis, simulating this axis alone.
+    /// The axis is back to its initial state afterwards
+    pub fn find_axis_cycle(&mut self, axis: usize) -> u64 {
+        let init_pos = self.get_axis_positions(axis);
+        let init_vel = self.get_axis_velocities(axis);
 
         let mut counter = 0;
         loop {
-            self.step();
+            self.step_axis(axis);
             counter += 1;
 
-            if repeat_x == 0
-                && init_pos_x == self.get_x_positions()
-                && init_vel == self.get_x_velocities()
-            {
-                repeat_x = counter;
-            }
-
-            if repeat_y == 0
-                && init_pos_y == self.get_y_positions()
-                && init_vel == self.get_y_velocities()
-            {
-                repeat_y = counter;
-            }
-
-            if repeat_z == 0
-                && init_pos_z == self.get_z_positions()
-                && init_vel == self.get_z_velocities()
+            if init_pos == self.get_axis_positions(axis)
+                && init_vel == self.get_axis_velocities(axis)
             {
-                repeat_z = counter;
-            }
-
-            if repeat_x != 0 && repeat_y != 0 && repeat_z != 0 {
-                break;
+                return counter;
             }
         }
+    }
 
-        repeat_x.lcm(&repeat_y).lcm(&repeat_z)
+    /// Find repeating cycles on independent coordinates,
+    /// then compute LCM between the 3
+    pub fn find_cycle(&mut self) -> u64 {
+        (0..3)
+            .map(|axis| self.find_axis_cycle(axis))
+            .fold(1, |acc, cycle| acc.lcm(&cycle))
     }
 
     pub fn compute_total_energy(&self) -> usize {
         self.moons.iter().map(|x| x.compute_total_energy()).sum()
     }
+
+    /// Compute total energy after `steps` steps, on a copy of the simulation
+    pub fn energy_at(&self, steps: usize) -> usize {
+        let mut sim = self.clone();
+        sim.step_for(steps);
+        sim.compute_total_energy()
+    }
 }
 
 fn part1(input_txt: &str) -> usize {
@@ -190,11 +262,11 @@ fn main() {
     let input_txt = include_str!("../input.txt");
 
     println!("[Part 1]");
-    let r = part1(&input_txt);
+    let r = part1(input_txt);
     println!("Result: {}", r);
 
     println!("[Part 2]");
-    let r = part2(&input_txt);
+    let r = part2(input_txt);
     println!("Result: {}", r);
 }
 
@@ -231,6 +303,36 @@ mod tests {
         assert_expr(&sim.moons[3], 3, 5, -1, 0, 0, 0);
     }
 
+    #[test]
+    fn test_parse_forgiving() {
+        assert_expr(&Moon::from_input("<x=1,y=-2,z=3>"), 1, -2, 3, 0, 0, 0);
+        assert_expr(
+            &Moon::from_input("  < x = -1, y=2 ,z=-3 >  "),
+            -1,
+            2,
+            -3,
+            0,
+            0,
+            0,
+        );
+
+        let sim = MoonSim::from_input("<x=1,y=2,z=3>\n<x=4, y=5, z=6> ");
+        assert_expr(&sim.moons[1], 4, 5, 6, 0, 0, 0);
+
+        assert_eq!(
+            Moon::try_from_input("<x=1,y=2>").err(),
+            Some("missing coordinate: z".to_owned())
+        );
+        assert_eq!(
+            Moon::try_from_input("<x=1,y=2,x=3,z=4>").err(),
+            Some("duplicate coordinate: x".to_owned())
+        );
+        assert_eq!(
+            Moon::try_from_input("<x=1,y=2,w=3>").err(),
+            Some("invalid coordinate name: \"w=3\"".to_owned())
+        );
+    }
+
     #[test]
     fn test_step() {
         let mut sim = MoonSim::from_input(example1());
@@ -256,10 +358,92 @@ mod tests {
         assert_eq!(sim.compute_total_energy(), 179);
     }
 
+    #[test]
+    fn test_step_unchanged() {
+        use itertools::Itertools;
+
+        // Previous implementation, cloning each pair of moons
+        fn reference_step(sim: &mut MoonSim) {
+            let combinations: Vec<Vec<usize>> = (0..sim.moons.len()).combinations(2).collect();
+            for combination in combinations {
+                let mut moon1 = sim.moons[combination[0]].clone();
+                let mut moon2 = sim.moons[combination[1]].clone();
+
+                moon1.apply_gravity(&mut moon2);
+
+                sim.moons[combination[0]] = moon1;
+                sim.moons[combination[1]] = moon2;
+            }
+
+            for moon in &mut sim.moons {
+                moon.integrate_velocity();
+            }
+        }
+
+        let mut sim = MoonSim::from_input(example1());
+        let mut reference = MoonSim::from_input(example1());
+        for _ in 0..1_000 {
+            sim.step();
+            reference_step(&mut reference);
+        }
+
+        for (moon, ref_moon) in sim.moons.iter().zip(reference.moons.iter()) {
+            assert_eq!(moon.position, ref_moon.position);
+            assert_eq!(moon.velocity, ref_moon.velocity);
+        }
+        assert_eq!(sim.compute_total_energy(), reference.compute_total_energy());
+    }
+
+    #[test]
+    fn test_step_axis() {
+        let mut sim = MoonSim::from_input(example1());
+        sim.step_axis(0);
+        assert_expr(&sim.moons[0], 2, 0, 2, 3, 0, 0);
+        assert_expr(&sim.moons[1], 3, -10, -7, 1, 0, 0);
+        assert_expr(&sim.moons[2], 1, -8, 8, -3, 0, 0);
+        assert_expr(&sim.moons[3], 2, 5, -1, -1, 0, 0);
+
+        sim.step_axis(1);
+        sim.step_axis(2);
+        let mut other_sim = MoonSim::from_input(example1());
+        other_sim.step();
+        for (moon, other_moon) in sim.moons.iter().zip(other_sim.moons.iter()) {
+            assert_eq!(moon.position, other_moon.position);
+            assert_eq!(moon.velocity, other_moon.velocity);
+        }
+    }
+
+    #[test]
+    fn test_energy_trace() {
+        let mut sim = MoonSim::from_input(example1());
+        let trace = sim.energy_trace(10);
+        assert_eq!(trace.len(), 10);
+        assert_eq!(trace[9], 179);
+
+        let mut other_sim = MoonSim::from_input(example1());
+        other_sim.step_for(10);
+        assert_eq!(trace[9], other_sim.compute_total_energy());
+    }
+
+    #[test]
+    fn test_energy_at() {
+        let sim = MoonSim::from_input(example1());
+        let initial_positions = sim.get_x_positions();
+        let initial_energy = sim.compute_total_energy();
+
+        assert_eq!(sim.energy_at(10), 179);
+        assert_eq!(sim.energy_at(0), initial_energy);
+        assert_eq!(sim.get_x_positions(), initial_positions);
+        assert_eq!(sim.get_axis_velocities(0), vec![0; 4]);
+        assert_eq!(sim.compute_total_energy(), initial_energy);
+    }
+
     #[test]
     fn test_cycles() {
         let mut sim = MoonSim::from_input(example1());
         assert_eq!(sim.find_cycle(), 2_772);
+        assert_eq!(sim.find_axis_cycle(0), 18);
+        assert_expr(&sim.moons[0], -1, 0, 2, 0, 0, 0);
 
         let mut sim = MoonSim::from_input(example2());
         assert_eq!(sim.find_cycle(), 4_686_774_924);
@@ -268,7 +452,7 @@ mod tests {
     #[test]
     fn test_results() {
         let input_txt = include_str!("../input.txt");
-        assert_eq!(part1(&input_txt), 8_960);
-        assert_eq!(part2(&input_txt), 314_917_503_970_904);
+        assert_eq!(part1(input_txt), 8_960);
+        assert_eq!(part2(input_txt), 314_917_503_970_904);
     }
 }
@@ -1,12 +1,15 @@
 use std::collections::HashMap;
 use std::env;
-use std::io::{stdout, Write};
+use std::fmt;
+use std::io::{self, stdout, Write};
+use std::str::FromStr;
 use std::time::Instant;
 
 use colored::Colorize;
-use crossterm::{cursor, style, terminal, ExecutableCommand, QueueableCommand};
+use crossterm::{cursor, terminal, Command};
 
 use common::interpreter::{ExecutionState, Interpreter};
+use common::io::split_csv;
 
 pub type Vector2D = euclid::default::Vector2D<i32>;
 
@@ -41,6 +44,17 @@ impl Tile {
         }
     }
 
+    /// Get uncolored glyph
+    pub fn to_char(self) -> char {
+        match self {
+            Tile::Empty => ' ',
+            Tile::Wall => '█',
+            Tile::Block => '#',
+            Tile::HorizontalPaddle => '=',
+            Tile::Ball => 'o',
+        }
+    }
+
     pub fn to_ascii(self) -> String {
         match self {
             Tile::Empty => " ".to_owned(),
@@ -52,6 +66,27 @@ impl Tile {
     }
 }
 
+impl fmt::Display for Tile {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.to_char())
+    }
+}
+
+impl FromStr for Tile {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            " " => Ok(Tile::Empty),
+            "█" => Ok(Tile::Wall),
+            "#" => Ok(Tile::Block),
+            "=" => Ok(Tile::HorizontalPaddle),
+            "o" => Ok(Tile::Ball),
+            _ => Err(format!("unknown tile: {}", s)),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum JoystickMovement {
     Neutral,
@@ -69,10 +104,18 @@ impl JoystickMovement {
     }
 }
 
+/// Game outcome: the game is won when no block is left
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PlayResult {
+    pub score: i32,
+    pub blocks_left: usize,
+}
+
 #[derive(Debug, Default)]
 pub struct Game {
     tiles: HashMap<Vector2D, Tile>,
     score: i32,
+    last_decision: Option<(Vector2D, Vector2D, JoystickMovement)>,
 }
 
 impl Game {
@@ -80,50 +123,132 @@ impl Game {
         Self::default()
     }
 
-    pub fn play(&mut self, code: &str, with_ui: bool) -> i32 {
-        let mut interpreter = Interpreter::new(code);
-        // Play for free!
-        interpreter.set_value(0, 2);
-
-        let mut stdout = stdout();
+    pub fn play(&mut self, code: &str, with_ui: bool) -> PlayResult {
         let start = Instant::now();
 
-        if with_ui {
+        let result = if with_ui {
             println!("Running game with UI ...");
+            self.play_to_writer(code, &mut stdout()).unwrap()
         } else {
             println!("Running game without UI ...");
+            let score = self.play_with_callback(code, |_| ());
+            PlayResult {
+                score,
+                blocks_left: self.blocks_remaining(),
+            }
+        };
+        println!("Game over: {} milliseconds", start.elapsed().as_millis());
+
+        result
+    }
+
+    /// Play game, writing each frame to `out`: a screen clear,
+    /// then the screen dump and the score
+    pub fn play_to_writer(&mut self, code: &str, out: &mut dyn Write) -> io::Result<PlayResult> {
+        let mut write_result = Ok(());
+        let score = self.play_with_callback(code, |game| {
+            // Stop writing after the first error
+            if write_result.is_ok() {
+                write_result = game.write_frame(out);
+            }
+        });
+        write_result?;
+
+        Ok(PlayResult {
+            score,
+            blocks_left: self.blocks_remaining(),
+        })
+    }
+
+    /// Play game without UI, updating tiles in place from the interpreter
+    /// output instead of rebuilding them on each frame
+    pub fn play_fast(&mut self, code: &str) -> i32 {
+        let mut interpreter = Interpreter::new(code);
+        // Play for free!
+        interpreter.set_value(0, 2);
+
+        loop {
+            let state = interpreter.run_until_halt();
+            self.apply_outputs(interpreter.get_output_stream());
+            interpreter.clear_output();
+
+            match state {
+                ExecutionState::Wait => {
+                    let movement = self.process_joystick_input();
+                    interpreter.push_input(movement.to_code());
+                }
+                _ => break,
+            }
         }
 
+        self.score
+    }
+
+    /// Play game, calling `callback` on each frame
+    pub fn play_with_callback<F>(&mut self, code: &str, callback: F) -> i32
+    where
+        F: FnMut(&Game),
+    {
+        self.play_with_policy(code, callback, |game| {
+            // Move the paddle depending on the ball position
+            Some(game.process_joystick_input())
+        })
+        .unwrap()
+    }
+
+    /// Play the game feeding a predetermined move sequence.
+    /// Fail if the sequence runs out before game over
+    pub fn simulate_moves(
+        &mut self,
+        code: &str,
+        moves: &[JoystickMovement],
+    ) -> Result<i32, String> {
+        let mut moves = moves.iter().copied();
+        self.play_with_policy(code, |_| (), |_| moves.next())
+    }
+
+    fn play_with_policy<F, P>(
+        &mut self,
+        code: &str,
+        mut callback: F,
+        mut policy: P,
+    ) -> Result<i32, String>
+    where
+        F: FnMut(&Game),
+        P: FnMut(&mut Game) -> Option<JoystickMovement>,
+    {
+        let mut interpreter = Interpreter::new(code);
+        // Play for free!
+        interpreter.set_value(0, 2);
+
         'game: loop {
             let (_, state) = interpreter.step();
             match state {
                 ExecutionState::Wait => {
                     // Read input from interpreter
-                    self.read_input(&interpreter.dump_output());
+                    self.read_outputs(interpreter.get_output_stream());
                     // Reimport new data in interpreter output
                     interpreter.set_output_values(self.dump_tiles());
 
-                    // Show game
-                    if with_ui {
-                        self.print_screen(&mut stdout);
-                    }
+                    callback(self);
 
-                    // Move the paddle depending on the ball position
-                    let movement = self.process_joystick_input();
+                    let movement = policy(self).ok_or("no moves left before game over")?;
                     interpreter.push_input(movement.to_code());
                 }
                 ExecutionState::Exit => {
                     // Read last input from interpreter
-                    self.read_input(&interpreter.dump_output());
+                    self.read_outputs(interpreter.get_output_stream());
+                    callback(self);
                     break 'game;
                 }
-                _ => (),
+                ExecutionState::Error(e) => return Err(format!("interpreter error: {:?}", e)),
+                ExecutionState::Timeout => return Err("interpreter timeout".to_owned()),
+                ExecutionState::Next => (),
             }
         }
-        println!("Game over: {} milliseconds", start.elapsed().as_millis());
 
         // Score
-        self.score
+        Ok(self.score)
     }
 
     pub fn get_single_tile_position(&self, tile: Tile) -> Vector2D {
@@ -142,24 +267,39 @@ impl Game {
         self.get_single_tile_position(Tile::HorizontalPaddle)
     }
 
-    pub fn process_joystick_input(&self) -> JoystickMovement {
+    pub fn process_joystick_input(&mut self) -> JoystickMovement {
         let ball_position = self.get_ball_position();
         let paddle_position = self.get_paddle_position();
 
-        if ball_position.x < paddle_position.x {
+        let movement = if ball_position.x < paddle_position.x {
             JoystickMovement::Left
         } else if ball_position.x > paddle_position.x {
             JoystickMovement::Right
         } else {
             JoystickMovement::Neutral
-        }
+        };
+
+        self.last_decision = Some((ball_position, paddle_position, movement));
+        movement
+    }
+
+    /// Get ball position, paddle position and movement
+    /// of the last `process_joystick_input` call
+    pub fn last_decision(&self) -> Option<(Vector2D, Vector2D, JoystickMovement)> {
+        self.last_decision
     }
 
     pub fn from_idle_intcode(code: &str) -> Self {
         let mut interpreter = Interpreter::new(code);
         interpreter.run();
 
-        Self::from_input(&interpreter.dump_output())
+        Self::from_interpreter_output(interpreter.get_output_stream())
+    }
+
+    pub fn from_interpreter_output(outputs: &[i64]) -> Self {
+        let mut game = Self::new();
+        game.read_outputs(outputs);
+        game
     }
 
     pub fn from_input(input: &str) -> Self {
@@ -169,39 +309,42 @@ impl Game {
     }
 
     pub fn read_input(&mut self, input: &str) {
-        let mut tiles = HashMap::new();
-        let entries: Vec<_> = input
-            .split(',')
-            .map(|x| x.parse::<i32>().unwrap())
+        let entries: Vec<_> = split_csv(input)
+            .into_iter()
+            .map(|x| x.parse::<i64>().unwrap())
             .collect();
-        let entries_count = entries.len();
 
-        let mut cursor = 0;
-        loop {
-            let x = entries[cursor];
-            let y = entries[cursor + 1];
-            let tile_id = entries[cursor + 2];
+        self.read_outputs(&entries);
+    }
+
+    /// Read (x, y, tile_id) triples, or (-1, 0, score) for score updates
+    pub fn read_outputs(&mut self, outputs: &[i64]) {
+        self.tiles.clear();
+        self.apply_outputs(outputs);
+    }
+
+    /// Update tiles and score from (x, y, tile_id) triples,
+    /// keeping tiles which are not updated
+    pub fn apply_outputs(&mut self, outputs: &[i64]) {
+        for entry in outputs.chunks_exact(3) {
+            let x = entry[0] as i32;
+            let y = entry[1] as i32;
+            let tile_id = entry[2] as i32;
 
             if x == -1 && y == 0 {
                 // Score update
                 self.score = tile_id;
             } else {
                 // New tile
-                tiles.insert(Vector2D::new(x, y), Tile::from_tile_id(tile_id));
-            }
-
-            cursor += 3;
-            if cursor >= entries_count {
-                break;
+                self.tiles
+                    .insert(Vector2D::new(x, y), Tile::from_tile_id(tile_id));
             }
         }
-
-        self.tiles = tiles;
     }
 
     pub fn get_screen_rect(&self) -> (Vector2D, Vector2D) {
-        let mut top_left = Vector2D::new(i32::max_value(), i32::max_value());
-        let mut bottom_right = Vector2D::new(i32::min_value(), i32::min_value());
+        let mut top_left = Vector2D::new(i32::MAX, i32::MAX);
+        let mut bottom_right = Vector2D::new(i32::MIN, i32::MIN);
 
         for coord in self.tiles.keys() {
             if coord.x < top_left.x {
@@ -237,39 +380,29 @@ impl Game {
         screen
     }
 
+    /// Dump tiles as (x, y, tile_id) triples, sorted by (y, x)
     pub fn dump_tiles(&self) -> Vec<i64> {
-        self.tiles.iter().fold(vec![], |mut ve, (k, v)| {
+        let mut positions: Vec<_> = self.tiles.keys().copied().collect();
+        positions.sort_by_key(|p| (p.y, p.x));
+
+        positions.iter().fold(vec![], |mut ve, k| {
             ve.push(k.x.into());
             ve.push(k.y.into());
-            ve.push(v.to_tile_id().into());
+            ve.push(self.tiles[k].to_tile_id().into());
             ve
         })
     }
 
-    pub fn print_screen(&self, stdout: &mut std::io::Stdout) {
-        let (top_left, bottom_right) = self.get_screen_rect();
-
-        stdout
-            .execute(terminal::Clear(terminal::ClearType::All))
-            .unwrap();
-        for y in top_left.y..bottom_right.y {
-            for x in top_left.x..bottom_right.x {
-                stdout
-                    .queue(cursor::MoveTo(x as u16, y as u16))
-                    .unwrap()
-                    .queue(style::Print(self.get_tile(x, y).to_ascii()))
-                    .unwrap();
-            }
-        }
-        stdout
-            .queue(cursor::MoveTo(
-                top_left.x as u16,
-                (bottom_right.y + 1) as u16,
-            ))
-            .unwrap()
-            .queue(style::Print(format!("Score: {}", self.score)))
-            .unwrap();
-        stdout.flush().unwrap();
+    fn write_frame(&self, out: &mut dyn Write) -> io::Result<()> {
+        write!(
+            out,
+            "{}{}{}",
+            terminal::Clear(terminal::ClearType::All).ansi_code(),
+            cursor::MoveTo(0, 0).ansi_code(),
+            self.dump_screen()
+        )?;
+        writeln!(out, "Score: {}", self.score)?;
+        out.flush()
     }
 
     pub fn get_tile(&self, x: i32, y: i32) -> Tile {
@@ -280,6 +413,10 @@ impl Game {
     pub fn count_tiles(&self, tile: Tile) -> usize {
         self.tiles.iter().filter(|&(_, v)| *v == tile).count()
     }
+
+    pub fn blocks_remaining(&self) -> usize {
+        self.count_tiles(Tile::Block)
+    }
 }
 
 fn part1(input_txt: &str) -> usize {
@@ -289,7 +426,11 @@ fn part1(input_txt: &str) -> usize {
 
 fn part2(input_txt: &str, with_ui: bool) -> i32 {
     let mut game = Game::new();
-    game.play(input_txt, with_ui)
+    if with_ui {
+        game.play(input_txt, with_ui).score
+    } else {
+        game.play_fast(input_txt)
+    }
 }
 
 fn main() {
@@ -298,11 +439,11 @@ fn main() {
     let with_ui = &args.get(1).cloned().unwrap_or_else(|| "".to_owned()) == "ui";
 
     println!("[Part 1]");
-    let r = part1(&input_txt);
+    let r = part1(input_txt);
     println!("Result: {}", r);
 
     println!("[Part 2]");
-    let r = part2(&input_txt, with_ui);
+    let r = part2(input_txt, with_ui);
     println!("Result: {}", r);
 }
 
@@ -317,11 +458,153 @@ mod tests {
         assert_eq!(game.get_tile(6, 5), Tile::Ball);
     }
 
+    #[test]
+    fn test_from_interpreter_output() {
+        let game = Game::from_interpreter_output(&[1, 2, 3]);
+        assert_eq!(game.get_tile(1, 2), Tile::HorizontalPaddle);
+        assert_eq!(game.count_tiles(Tile::HorizontalPaddle), 1);
+
+        let game = Game::from_interpreter_output(&[-1, 0, 42, 0, 0, 2]);
+        assert_eq!(game.score, 42);
+        assert_eq!(game.blocks_remaining(), 1);
+    }
+
+    #[test]
+    fn test_dump_tiles() {
+        let input = "5,1,1,0,0,2,3,0,4,1,1,3,2,0,1";
+        let first = Game::from_input(input).dump_tiles();
+        let second = Game::from_input(input).dump_tiles();
+        assert_eq!(first, second);
+        assert_eq!(first, vec![0, 0, 2, 2, 0, 1, 3, 0, 4, 1, 1, 3, 5, 1, 1]);
+    }
+
+    #[test]
+    fn test_tile_text() {
+        for tile_id in 0..5 {
+            let tile = Tile::from_tile_id(tile_id);
+            assert_eq!(tile.to_string().parse::<Tile>(), Ok(tile));
+        }
+        assert!("x".parse::<Tile>().is_err());
+    }
+
+    /// Draw a block, a paddle and a ball, wait for a joystick input,
+    /// then clear the block.
+    /// The first instruction is a no-op arithmetic as address 0 is
+    /// overwritten when playing.
+    fn stub_game() -> &'static str {
+        "1,100,100,100,104,1,104,0,104,2,104,0,104,1,104,3,104,2,104,1,104,4,\
+         3,100,104,1,104,0,104,0,99"
+    }
+
+    #[test]
+    fn test_blocks_over_time() {
+        let mut game = Game::new();
+        let mut blocks = vec![];
+        game.play_with_callback(stub_game(), |g| blocks.push(g.blocks_remaining()));
+
+        assert_eq!(blocks, vec![1, 0]);
+        assert!(blocks.windows(2).all(|w| w[1] <= w[0]));
+    }
+
+    #[test]
+    fn test_play_result() {
+        let mut game = Game::new();
+        assert_eq!(
+            game.play(stub_game(), false),
+            PlayResult {
+                score: 0,
+                blocks_left: 0
+            }
+        );
+
+        // Ball lost with a block left
+        let code = "1,100,100,100,104,1,104,0,104,2,104,-1,104,0,104,7,99";
+        let mut game = Game::new();
+        assert_eq!(
+            game.play(code, false),
+            PlayResult {
+                score: 7,
+                blocks_left: 1
+            }
+        );
+    }
+
+    #[test]
+    fn test_last_decision() {
+        let mut game = Game::from_interpreter_output(&[0, 0, 3, 2, 1, 4]);
+        assert_eq!(game.last_decision(), None);
+        assert_eq!(game.process_joystick_input(), JoystickMovement::Right);
+        assert_eq!(
+            game.last_decision(),
+            Some((
+                Vector2D::new(2, 1),
+                Vector2D::new(0, 0),
+                JoystickMovement::Right
+            ))
+        );
+
+        let mut game = Game::new();
+        let mut decisions = vec![];
+        game.play_with_callback(stub_game(), |g| decisions.push(g.last_decision()));
+        decisions.push(game.last_decision());
+
+        // Each decision moves the paddle toward the ball
+        for (ball, paddle, movement) in decisions.into_iter().flatten() {
+            let expected = match ball.x.cmp(&paddle.x) {
+                std::cmp::Ordering::Less => JoystickMovement::Left,
+                std::cmp::Ordering::Greater => JoystickMovement::Right,
+                std::cmp::Ordering::Equal => JoystickMovement::Neutral,
+            };
+            assert_eq!(movement, expected);
+        }
+        assert!(game.last_decision().is_some());
+    }
+
+    #[test]
+    fn test_play_to_writer() {
+        let mut game = Game::new();
+        let mut out = vec![];
+        let result = game.play_to_writer(stub_game(), &mut out).unwrap();
+        assert_eq!(result, Game::new().play(stub_game(), false));
+
+        let output = String::from_utf8(out).unwrap();
+        let clear = terminal::Clear(terminal::ClearType::All).ansi_code();
+        let frames: Vec<&str> = output.split(clear).skip(1).collect();
+        assert_eq!(frames.len(), 2);
+        assert!(frames[1].ends_with(&format!("{}Score: 0\n", game.dump_screen())));
+    }
+
+    #[test]
+    fn test_play_fast() {
+        let mut game = Game::new();
+        let score = game.play(stub_game(), false).score;
+
+        let mut fast_game = Game::new();
+        assert_eq!(fast_game.play_fast(stub_game()), score);
+        assert_eq!(fast_game.dump_tiles(), game.dump_tiles());
+    }
+
+    #[test]
+    fn test_simulate_moves() {
+        let mut game = Game::new();
+        assert_eq!(
+            game.simulate_moves(stub_game(), &[JoystickMovement::Left]),
+            Ok(0)
+        );
+        assert_eq!(game.blocks_remaining(), 0);
+
+        // Score is the joystick input
+        let code = "1,100,100,100,104,0,104,0,104,3,104,1,104,0,104,4,\
+                    3,100,104,-1,104,0,4,100,99";
+        let mut game = Game::new();
+        assert_eq!(game.simulate_moves(code, &[JoystickMovement::Right]), Ok(1));
+        assert!(game.simulate_moves(code, &[]).is_err());
+    }
+
     #[test]
     fn test_results() {
         let input_txt = include_str!("../input.txt");
-        assert_eq!(part1(&input_txt), 253);
-        // Execution time: ~30 seconds
-        assert_eq!(part2(&input_txt, false), 12_263);
+        assert_eq!(part1(input_txt), 253);
+        assert_eq!(part2(input_txt, false), 12_263);
     }
 }
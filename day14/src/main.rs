@@ -1,4 +1,6 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+
+use common::io::split_lines;
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Chemical {
@@ -21,7 +23,7 @@ impl Chemical {
     pub fn from_input(input: &str) -> Self {
         let entry: Vec<&str> = input.split(' ').collect();
         let value = entry
-            .get(0)
+            .first()
             .and_then(|x| x.parse::<i64>().ok())
             .unwrap_or_else(|| panic!("invalid chemical value {:?}", entry));
         let name = entry
@@ -64,7 +66,10 @@ impl Simulation {
     }
 
     pub fn from_input(input: &str) -> Self {
-        let reactions: Vec<_> = input.split('\n').map(Reaction::from_input).collect();
+        let reactions: Vec<_> = split_lines(input)
+            .into_iter()
+            .map(Reaction::from_input)
+            .collect();
         Self { reactions }
     }
 
@@ -76,23 +81,131 @@ impl Simulation {
             .unwrap_or_else(|| panic!("unknown output variable: {}", var_name))
     }
 
+    /// List output names of reactions consuming a chemical
+    pub fn consumers_of(&self, name: &str) -> Vec<String> {
+        self.reactions
+            .iter()
+            .filter(|reaction| reaction.input.iter().any(|c| c.name == name))
+            .map(|reaction| reaction.output.name.clone())
+            .collect()
+    }
+
+    /// Check that each input chemical is produced by exactly one reaction,
+    /// and that reactions do not depend on themselves
+    pub fn validate(&self) -> Result<(), String> {
+        let mut producers: HashMap<&str, usize> = HashMap::new();
+        for reaction in &self.reactions {
+            *producers.entry(&reaction.output.name).or_insert(0) += 1;
+        }
+
+        for reaction in &self.reactions {
+            for chemical in reaction.input.iter().filter(|c| !c.is_ore()) {
+                match producers.get(chemical.name.as_str()) {
+                    None => return Err(format!("missing reaction for {}", chemical.name)),
+                    Some(1) => (),
+                    Some(count) => {
+                        return Err(format!("{} reactions for {}", count, chemical.name));
+                    }
+                }
+            }
+        }
+
+        // Depth-first search, keeping the current path to detect cycles
+        let mut visited: HashSet<&str> = HashSet::new();
+        for reaction in &self.reactions {
+            let mut path = vec![];
+            self.check_cycle(&reaction.output.name, &mut path, &mut visited)?;
+        }
+
+        Ok(())
+    }
+
+    fn check_cycle<'a>(
+        &'a self,
+        name: &'a str,
+        path: &mut Vec<&'a str>,
+        visited: &mut HashSet<&'a str>,
+    ) -> Result<(), String> {
+        if path.contains(&name) {
+            return Err(format!("cyclic reaction for {}", name));
+        }
+
+        if !visited.insert(name) {
+            return Ok(());
+        }
+
+        if let Some(reaction) = self.reactions.iter().find(|x| x.output.name == name) {
+            path.push(name);
+            for chemical in reaction.input.iter().filter(|c| !c.is_ore()) {
+                self.check_cycle(&chemical.name, path, visited)?;
+            }
+            path.pop();
+        }
+
+        Ok(())
+    }
+
     pub fn find_fuel_reaction(&self) -> Reaction {
         self.find_reaction_for("FUEL")
     }
 
     pub fn calculate_single_fuel(&self) -> i64 {
-        let mut map = HashMap::new();
-        self.calculate_fuel_quantity(1, &mut map)
+        self.calculate_cost_for("FUEL", 1)
+    }
+
+    /// Calculate ORE needed for `quantity` of any chemical
+    pub fn calculate_cost_for(&self, target: &str, quantity: i64) -> i64 {
+        let mut remaining = HashMap::new();
+        self.calculate_cost_with_index(&self.build_index(), target, quantity, &mut remaining)
+    }
+
+    /// Calculate ORE needed for one FUEL, with the leftover quantity
+    /// of each produced chemical
+    pub fn calculate_fuel_detailed(&self) -> (i64, HashMap<String, i64>) {
+        let mut remaining = HashMap::new();
+        let ore = self.calculate_fuel_quantity(1, &mut remaining);
+        remaining.retain(|_, quantity| *quantity > 0);
+
+        (ore, remaining)
+    }
+
+    /// Index reactions by output chemical name
+    pub fn build_index(&self) -> HashMap<String, &Reaction> {
+        self.reactions
+            .iter()
+            .map(|reaction| (reaction.output.name.clone(), reaction))
+            .collect()
     }
 
     pub fn calculate_fuel_quantity(
         &self,
         fuel_quantity: i64,
         remaining: &mut HashMap<String, i64>,
+    ) -> i64 {
+        self.calculate_fuel_quantity_with_index(&self.build_index(), fuel_quantity, remaining)
+    }
+
+    /// Same as `calculate_fuel_quantity`, using a prebuilt reaction index
+    pub fn calculate_fuel_quantity_with_index(
+        &self,
+        index: &HashMap<String, &Reaction>,
+        fuel_quantity: i64,
+        remaining: &mut HashMap<String, i64>,
+    ) -> i64 {
+        self.calculate_cost_with_index(index, "FUEL", fuel_quantity, remaining)
+    }
+
+    /// Calculate ORE needed for `quantity` of `target`, using a prebuilt reaction index
+    fn calculate_cost_with_index(
+        &self,
+        index: &HashMap<String, &Reaction>,
+        target: &str,
+        quantity: i64,
+        remaining: &mut HashMap<String, i64>,
     ) -> i64 {
         let mut needed = Vec::new();
         let mut ore = 0;
-        needed.push(("FUEL".to_owned(), fuel_quantity));
+        needed.push((target.to_owned(), quantity));
 
         while !needed.is_empty() {
             let (needed_name, mut needed_quantity) = needed.remove(0);
@@ -117,7 +230,9 @@ impl Simulation {
 
             if needed_quantity > 0 {
                 // Get reaction & calculate coef
-                let reaction = self.find_reaction_for(&needed_name);
+                let reaction = index
+                    .get(&needed_name)
+                    .unwrap_or_else(|| panic!("unknown output variable: {}", needed_name));
                 let div = ((needed_quantity - 1) / reaction.output.value) + 1;
                 *remaining_quantity = reaction.output.value * div - needed_quantity;
 
@@ -132,8 +247,9 @@ impl Simulation {
     }
 
     fn calculate_fuel_from_ore(&self, base_ore: i64) -> i64 {
+        let index = self.build_index();
         let mut remaining = HashMap::new();
-        let ore_per_fuel = self.calculate_fuel_quantity(1, &mut remaining);
+        let ore_per_fuel = self.calculate_fuel_quantity_with_index(&index, 1, &mut remaining);
         let mut total_fuel = 1;
 
         // Reinject ore in remaining
@@ -146,7 +262,7 @@ impl Simulation {
                 fuel_amount = 1;
             }
 
-            let ore = self.calculate_fuel_quantity(fuel_amount, &mut remaining);
+            let ore = self.calculate_fuel_quantity_with_index(&index, fuel_amount, &mut remaining);
             // Remaining ore? Done.
             if ore > 0 {
                 break;
@@ -171,11 +287,11 @@ fn main() {
     let input_txt = include_str!("../input.txt");
 
     println!("[Part 1]");
-    let r = part1(&input_txt);
+    let r = part1(input_txt);
     println!("Result: {}", r);
 
     println!("[Part 2]");
-    let r = part2(&input_txt);
+    let r = part2(input_txt);
     println!("Result: {}", r);
 }
 
@@ -270,6 +386,44 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_consumers_of() {
+        let sim = Simulation::from_input(example1());
+        assert_eq!(
+            sim.consumers_of("A"),
+            vec![
+                "C".to_owned(),
+                "D".to_owned(),
+                "E".to_owned(),
+                "FUEL".to_owned()
+            ]
+        );
+        assert_eq!(
+            sim.consumers_of("ORE"),
+            vec!["A".to_owned(), "B".to_owned()]
+        );
+        assert!(sim.consumers_of("FUEL").is_empty());
+    }
+
+    #[test]
+    fn test_validate() {
+        assert_eq!(Simulation::from_input(example1()).validate(), Ok(()));
+        assert_eq!(Simulation::from_input(example5()).validate(), Ok(()));
+
+        assert_eq!(
+            Simulation::from_input("10 ORE => 10 A\n7 A, 1 B => 1 FUEL").validate(),
+            Err("missing reaction for B".to_owned())
+        );
+        assert_eq!(
+            Simulation::from_input("1 ORE => 1 A\n1 ORE => 2 A\n1 A => 1 FUEL").validate(),
+            Err("2 reactions for A".to_owned())
+        );
+        assert_eq!(
+            Simulation::from_input("1 ORE, 1 B => 1 A\n1 A => 1 B\n1 A => 1 FUEL").validate(),
+            Err("cyclic reaction for A".to_owned())
+        );
+    }
+
     #[test]
     fn test_resolution_small() {
         assert_eq!(
@@ -298,6 +452,53 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_resolution_detailed() {
+        let (ore, remaining) = Simulation::from_input(example1()).calculate_fuel_detailed();
+        assert_eq!(ore, 31);
+        // 28 A are needed, produced by batches of 10
+        assert_eq!(remaining.get("A"), Some(&2));
+        assert_eq!(remaining.len(), 1);
+    }
+
+    #[test]
+    fn test_cost_for() {
+        let sim = Simulation::from_input(example1());
+        assert_eq!(sim.calculate_cost_for("FUEL", 1), 31);
+        assert_eq!(sim.calculate_cost_for("B", 3), 3);
+        assert_eq!(sim.calculate_cost_for("A", 10), 10);
+        assert_eq!(sim.calculate_cost_for("A", 11), 20);
+        // 7 A and 1 B
+        assert_eq!(sim.calculate_cost_for("C", 1), 11);
+        // 14 A and 1 B
+        assert_eq!(sim.calculate_cost_for("D", 1), 21);
+        assert_eq!(sim.calculate_cost_for("ORE", 5), 5);
+    }
+
+    #[test]
+    fn test_resolution_with_index() {
+        let examples = [
+            (example1(), 31),
+            (example2(), 165),
+            (example3(), 13_312),
+            (example4(), 180_697),
+            (example5(), 2_210_736),
+        ];
+
+        for (example, ore) in examples.iter() {
+            let sim = Simulation::from_input(example);
+            let index = sim.build_index();
+            assert_eq!(index.len(), sim.reactions.len());
+            assert_eq!(index["FUEL"], &sim.find_fuel_reaction());
+
+            let mut remaining = HashMap::new();
+            assert_eq!(
+                sim.calculate_fuel_quantity_with_index(&index, 1, &mut remaining),
+                *ore
+            );
+        }
+    }
+
     #[test]
     fn test_fuel_with_small() {
         let amount = 1_000_000_000_000;
@@ -318,7 +519,7 @@ mod tests {
     #[test]
     fn test_results() {
         let input_txt = include_str!("../input.txt");
-        assert_eq!(part1(&input_txt), 443_537);
-        assert_eq!(part2(&input_txt), 2_910_558);
+        assert_eq!(part1(input_txt), 443_537);
+        assert_eq!(part2(input_txt), 2_910_558);
     }
 }
@@ -1,7 +1,9 @@
+use common::geometry::{Direction, Vector2D};
 use common::interpreter::{ExecutionState, Interpreter};
 use std::collections::{HashMap, HashSet};
-
-type Vector2D = euclid::default::Vector2D<i32>;
+use std::convert::TryFrom;
+use std::fmt;
+use std::str::FromStr;
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum Tile {
@@ -11,24 +13,34 @@ pub enum Tile {
     Way,
 }
 
+/// Tile and interpreter code pairs
+const TILE_CODES: [(Tile, i64); 4] = [
+    (Tile::Wall, 0),
+    (Tile::Empty, 1),
+    (Tile::Oxygen, 2),
+    (Tile::Way, 9),
+];
+
 impl Tile {
-    pub fn from_code(code: i64) -> Self {
-        match code {
-            0 => Self::Wall,
-            1 => Self::Empty,
-            2 => Self::Oxygen,
-            9 => Self::Way,
-            _ => panic!("unknown tile code: {}", code),
-        }
+    /// Get every tile, in code table order
+    pub fn all() -> [Tile; 4] {
+        [Tile::Wall, Tile::Empty, Tile::Oxygen, Tile::Way]
+    }
+
+    pub fn from_code(code: i64) -> Result<Self, String> {
+        TILE_CODES
+            .iter()
+            .find(|&&(_, c)| c == code)
+            .map(|&(tile, _)| tile)
+            .ok_or_else(|| format!("unknown tile code: {}", code))
     }
 
     pub fn to_code(self) -> i64 {
-        match self {
-            Self::Wall => 0,
-            Self::Empty => 1,
-            Self::Oxygen => 2,
-            Self::Way => 9,
-        }
+        TILE_CODES
+            .iter()
+            .find(|&&(tile, _)| tile == self)
+            .map(|&(_, code)| code)
+            .expect("tile should be in code table")
     }
 
     pub fn to_ascii(self) -> String {
@@ -43,71 +55,72 @@ impl Tile {
     }
 }
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq)]
-pub enum Direction {
-    North,
-    East,
-    South,
-    West,
-}
-
-impl Direction {
-    pub fn from_code(code: i64) -> Self {
-        match code {
-            1 => Self::North,
-            2 => Self::South,
-            3 => Self::West,
-            4 => Self::East,
-            _ => panic!("unknown direction code: {}", code),
-        }
-    }
+impl TryFrom<i64> for Tile {
+    type Error = String;
 
-    pub fn list() -> Vec<Self> {
-        vec![Self::East, Self::West, Self::North, Self::South]
+    fn try_from(code: i64) -> Result<Self, Self::Error> {
+        Self::from_code(code)
     }
+}
 
-    pub fn to_offset(self) -> Vector2D {
-        match self {
-            Self::North => Vector2D::new(0, 1),
-            Self::South => Vector2D::new(0, -1),
-            Self::East => Vector2D::new(1, 0),
-            Self::West => Vector2D::new(-1, 0),
-        }
+impl fmt::Display for Tile {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.to_ascii())
     }
+}
 
-    pub fn invert(self) -> Self {
-        match self {
-            Self::North => Self::South,
-            Self::East => Self::West,
-            Self::South => Self::North,
-            Self::West => Self::East,
-        }
-    }
+impl FromStr for Tile {
+    type Err = String;
 
-    pub fn to_code(self) -> i64 {
-        match self {
-            Self::North => 1,
-            Self::South => 2,
-            Self::West => 3,
-            Self::East => 4,
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "█" => Ok(Self::Wall),
+            "." => Ok(Self::Empty),
+            "O" => Ok(Self::Oxygen),
+            "@" => Ok(Self::Way),
+            _ => Err(format!("unknown tile: {}", s)),
         }
     }
 }
 
 pub struct Simulation {
-    code: String,
+    interpreter: Interpreter,
+    position: Vector2D,
+    path: Vec<Direction>,
+    oxygen_path: Vec<Direction>,
+    to_visit: Vec<Direction>,
+    visited: HashSet<Vector2D>,
+    tiles: HashMap<Vector2D, Tile>,
+    backtrack: Option<Direction>,
+    finished: bool,
 }
 
 impl Simulation {
     pub fn from_input(code: &str) -> Self {
+        let position = Vector2D::new(0, 0);
+        let mut visited = HashSet::new();
+        let mut tiles = HashMap::new();
+
+        // Initial position
+        visited.insert(position);
+        tiles.insert(position, Tile::Empty);
+
         Self {
-            code: code.to_owned(),
+            interpreter: Interpreter::new(code),
+            position,
+            path: vec![],
+            oxygen_path: vec![],
+            to_visit: vec![Direction::North],
+            visited,
+            tiles,
+            backtrack: None,
+            finished: false,
         }
     }
 
     pub fn get_rect(&self, tiles: &HashMap<Vector2D, Tile>) -> (Vector2D, Vector2D) {
-        let mut top_left = Vector2D::new(i32::max_value(), i32::max_value());
-        let mut bottom_right = Vector2D::new(i32::min_value(), i32::min_value());
+        let mut top_left = Vector2D::new(i32::MAX, i32::MAX);
+        let mut bottom_right = Vector2D::new(i32::MIN, i32::MIN);
 
         for coord in tiles.keys() {
             if coord.x < top_left.x {
@@ -128,20 +141,25 @@ impl Simulation {
         (top_left, bottom_right + Vector2D::new(1, 1))
     }
 
-    pub fn show_map(&self, tiles: &HashMap<Vector2D, Tile>, position: Vector2D) {
+    /// Render map as text, with the robot position marked as `x`
+    pub fn render_map(
+        &self,
+        tiles: &HashMap<Vector2D, Tile>,
+        position: Option<Vector2D>,
+    ) -> String {
         let (top_left, bottom_right) = self.get_rect(tiles);
         let mut screen = String::new();
 
         for y in top_left.y..bottom_right.y {
             for x in top_left.x..bottom_right.x {
-                if position.x == x && position.y == y {
+                if position == Some(Vector2D::new(x, y)) {
                     screen.push('x');
                 } else {
                     screen.push_str(
                         &tiles
                             .get(&Vector2D::new(x, y))
                             .copied()
-                            .unwrap_or_else(|| Tile::Empty)
+                            .unwrap_or(Tile::Empty)
                             .to_ascii(),
                     );
                 }
@@ -150,151 +168,174 @@ impl Simulation {
             screen.push('\n');
         }
 
+        screen
+    }
+
+    pub fn show_map(&self, tiles: &HashMap<Vector2D, Tile>, position: Vector2D) {
+        let screen = self.render_map(tiles, Some(position));
+
         std::thread::sleep(std::time::Duration::from_millis(50));
         // Shakes a little but it does the job
         print!("{}[2J", 27 as char);
         println!("{}", screen);
     }
 
-    pub fn run(
-        &mut self,
-        stop_at_oxygen: bool,
-        debug: bool,
-    ) -> (Vec<Direction>, HashMap<Vector2D, Tile>) {
-        let mut path = vec![];
-        let mut oxygen_path = vec![];
-        let mut to_visit = vec![];
-        let mut visited = HashSet::new();
-        let mut tiles = HashMap::new();
-        let mut interpreter = Interpreter::new(&self.code);
-        let mut position = Vector2D::new(0, 0);
-        let mut backtrack: Option<Direction> = None;
-
-        // Initial position
-        visited.insert(position.clone());
-        to_visit.push(Direction::North);
-        tiles.insert(position.clone(), Tile::Empty);
-
-        'simulation: loop {
+    /// Explore until a new tile is discovered.
+    /// Return `None` when the exploration is complete, and an error
+    /// when the interpreter does not output a valid tile code
+    pub fn explore_step(&mut self) -> Result<Option<(Vector2D, Tile)>, String> {
+        while !self.finished {
             // Let's go !
-            let direction = if let Some(mvmt) = backtrack {
-                mvmt.invert()
-            } else if let Some(mvmt) = to_visit.pop() {
+            let is_backtracking = self.backtrack.is_some();
+            let direction = if let Some(mvmt) = self.backtrack.take() {
+                mvmt.opposite()
+            } else if let Some(mvmt) = self.to_visit.pop() {
                 mvmt
             } else {
-                break 'simulation;
+                self.finished = true;
+                break;
             };
 
             // Move!
-            if backtrack.is_none() {
-                path.push(direction);
-            } else {
-                backtrack = None;
+            if !is_backtracking {
+                self.path.push(direction);
             }
 
-            position += direction.to_offset();
-            visited.insert(position);
-            interpreter.push_input(direction.to_code());
+            self.position += direction.to_offset();
+            self.visited.insert(self.position);
+            self.interpreter.push_input(direction.to_movement_code());
 
-            'internal: loop {
-                let (_, state) = interpreter.step();
-                match state {
-                    ExecutionState::Exit => {
-                        break 'simulation;
-                    }
-                    ExecutionState::Wait => {
-                        break 'internal;
-                    }
-                    _ => (),
-                }
+            if self.interpreter.run_until_halt() == ExecutionState::Exit {
+                self.finished = true;
+                break;
             }
 
             ///////////////////////////////////////////////
             // Waiting for input / Handling previous output
 
             // Movement
-            let output_tile = interpreter
+            let position = self.position;
+            let output_tile = self
+                .interpreter
                 .pop_output()
-                .map(Tile::from_code)
-                .expect("empty interpreter output");
-            tiles.insert(position.clone(), output_tile);
-            if debug {
-                self.show_map(&tiles, position + direction.invert().to_offset());
-            }
+                .ok_or_else(|| "empty interpreter output".to_owned())
+                .and_then(Tile::from_code)?;
+            self.tiles.insert(position, output_tile);
 
             match output_tile {
                 Tile::Wall => {
                     // Cannot pass, move back!
-                    let dir = path.pop().unwrap();
-                    position += dir.invert().to_offset();
+                    let dir = self.path.pop().unwrap();
+                    self.position += dir.opposite().to_offset();
                 }
                 Tile::Oxygen => {
                     // Ok!
-                    oxygen_path = path.clone();
-                    if stop_at_oxygen {
-                        println!("Oxygen found");
-                        break;
-                    }
+                    self.oxygen_path = self.path.clone();
                 }
                 _ => (),
             }
 
             // Check for other movements
-            for dir in Direction::list() {
-                let tgt = position + dir.to_offset();
-                if !visited.contains(&tgt) {
-                    to_visit.push(dir);
+            for dir in Direction::all().iter().copied() {
+                let tgt = self.position + dir.to_offset();
+                if !self.visited.contains(&tgt) {
+                    self.to_visit.push(dir);
                     break;
                 }
             }
 
-            if to_visit.is_empty() {
+            if self.to_visit.is_empty() {
                 // Go back
-                backtrack = path.pop();
+                self.backtrack = self.path.pop();
+            }
+
+            if !is_backtracking {
+                return Ok(Some((position, output_tile)));
             }
         }
 
-        let mut show_map_tiles = tiles.clone();
-        let mut path_pos = Vector2D::new(0, 0);
-        // Compute path
-        for p in &oxygen_path {
-            show_map_tiles.insert(path_pos.clone(), Tile::Way);
-            path_pos += p.to_offset();
+        Ok(None)
+    }
+
+    /// Explore the whole map, without any terminal output
+    pub fn explore(&mut self) -> Result<HashMap<Vector2D, Tile>, String> {
+        while self.explore_step()?.is_some() {}
+
+        Ok(self.tiles.clone())
+    }
+
+    /// Get path from start to oxygen, once discovered
+    pub fn path_to_oxygen(&self) -> Vec<Direction> {
+        self.oxygen_path.clone()
+    }
+
+    pub fn run(
+        &mut self,
+        stop_at_oxygen: bool,
+        debug: bool,
+    ) -> Result<(Vec<Direction>, HashMap<Vector2D, Tile>), String> {
+        while let Some((_, tile)) = self.explore_step()? {
+            if debug {
+                self.show_map(&self.tiles, self.position);
+            }
+
+            if tile == Tile::Oxygen && stop_at_oxygen {
+                if debug {
+                    println!("Oxygen found");
+                }
+                break;
+            }
         }
 
-        show_map_tiles.insert(Vector2D::new(0, 0), Tile::Oxygen);
-        self.show_map(&show_map_tiles, position);
+        if debug {
+            let mut show_map_tiles = self.tiles.clone();
+            let mut path_pos = Vector2D::new(0, 0);
+            // Compute path
+            for p in &self.oxygen_path {
+                show_map_tiles.insert(path_pos, Tile::Way);
+                path_pos += p.to_offset();
+            }
+
+            show_map_tiles.insert(Vector2D::new(0, 0), Tile::Oxygen);
+            self.show_map(&show_map_tiles, self.position);
+        }
 
-        (oxygen_path, tiles)
+        Ok((self.path_to_oxygen(), self.tiles.clone()))
     }
 
     pub fn fill_oxygen(&self, tiles: &mut HashMap<Vector2D, Tile>) -> usize {
+        self.fill_oxygen_steps(tiles, usize::MAX).0
+    }
+
+    /// Fill oxygen for at most `max_minutes`.
+    /// Return elapsed minutes and remaining empty tiles
+    pub fn fill_oxygen_steps(
+        &self,
+        tiles: &mut HashMap<Vector2D, Tile>,
+        max_minutes: usize,
+    ) -> (usize, usize) {
         let mut oxygen_points: Vec<Vector2D> = vec![];
-        let mut remaining_tiles: Vec<Vector2D> = vec![];
+        let mut remaining_tiles = 0;
 
         for (pos, tile) in tiles.iter() {
             if *tile == Tile::Oxygen {
-                oxygen_points.push(pos.clone());
+                oxygen_points.push(*pos);
             } else if *tile == Tile::Empty {
-                remaining_tiles.push(pos.clone());
+                remaining_tiles += 1;
             }
         }
 
         let mut time = 0;
-        while !remaining_tiles.is_empty() {
+        while remaining_tiles > 0 && time < max_minutes {
             let mut next_oxygen_points = vec![];
-            while !oxygen_points.is_empty() {
-                let point = oxygen_points.remove(0);
-                for dir in Direction::list() {
+            for point in oxygen_points {
+                for dir in Direction::all().iter().copied() {
                     let tgt = point + dir.to_offset();
                     if *tiles.get(&tgt).expect("tile should exist") == Tile::Empty {
                         // Remove remaining tile
                         tiles.insert(tgt, Tile::Oxygen);
-                        next_oxygen_points.push(tgt.clone());
-
-                        if let Some(x) = remaining_tiles.iter().position(|&x| x == tgt) {
-                            remaining_tiles.remove(x);
-                        }
+                        next_oxygen_points.push(tgt);
+                        remaining_tiles -= 1;
                     }
                 }
             }
@@ -303,19 +344,19 @@ impl Simulation {
             time += 1;
         }
 
-        time
+        (time, remaining_tiles)
     }
 }
 
 fn part1(input_txt: &str) -> usize {
     let mut sim = Simulation::from_input(input_txt);
-    let (path, _) = sim.run(true, false);
+    let (path, _) = sim.run(true, false).unwrap();
     path.len()
 }
 
 fn part2(input_txt: &str) -> usize {
     let mut sim = Simulation::from_input(input_txt);
-    let (_, mut tiles) = sim.run(false, false);
+    let mut tiles = sim.explore().unwrap();
     sim.fill_oxygen(&mut tiles)
 }
 
@@ -323,11 +364,11 @@ fn main() {
     let input_txt = include_str!("../input.txt");
 
     println!("[Part 1]");
-    let r = part1(&input_txt);
+    let r = part1(input_txt);
     println!("Result: {}", r);
 
     println!("[Part 2]");
-    let r = part2(&input_txt);
+    let r = part2(input_txt);
     println!("Result: {}", r);
 }
 
@@ -335,10 +376,114 @@ fn main() {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_render_map() {
+        let sim = Simulation::from_input("99");
+        let mut tiles = HashMap::new();
+        for y in -1..=1 {
+            for x in -1..=1 {
+                tiles.insert(Vector2D::new(x, y), Tile::Wall);
+            }
+        }
+        tiles.insert(Vector2D::new(0, 0), Tile::Empty);
+        tiles.insert(Vector2D::new(1, 0), Tile::Oxygen);
+
+        assert_eq!(sim.render_map(&tiles, None), "███\n█.O\n███\n");
+        assert_eq!(
+            sim.render_map(&tiles, Some(Vector2D::new(0, 0))),
+            "███\n█xO\n███\n"
+        );
+    }
+
+    #[test]
+    fn test_fill_oxygen_steps() {
+        let sim = Simulation::from_input("99");
+        let mut tiles = HashMap::new();
+        for y in -1..=1 {
+            for x in -1..=5 {
+                tiles.insert(Vector2D::new(x, y), Tile::Wall);
+            }
+        }
+        for x in 0..5 {
+            tiles.insert(Vector2D::new(x, 0), Tile::Empty);
+        }
+        tiles.insert(Vector2D::new(0, 0), Tile::Oxygen);
+
+        let mut partial_tiles = tiles.clone();
+        assert_eq!(sim.fill_oxygen_steps(&mut partial_tiles, 2), (2, 2));
+        assert_eq!(
+            sim.render_map(&partial_tiles, None).lines().nth(1),
+            Some("█OOO..█")
+        );
+
+        assert_eq!(sim.fill_oxygen_steps(&mut partial_tiles, 10), (2, 0));
+        assert_eq!(sim.fill_oxygen(&mut tiles), 4);
+    }
+
+    #[test]
+    fn test_tile_conversions() {
+        assert_eq!(Tile::try_from(Tile::Wall.to_code()), Ok(Tile::Wall));
+        assert!(Tile::try_from(5).is_err());
+        assert_eq!(Tile::from_code(9), Ok(Tile::Way));
+        assert_eq!(Tile::from_code(7), Err("unknown tile code: 7".to_owned()));
+
+        for tile in Tile::all().iter() {
+            assert_eq!(Tile::from_code(tile.to_code()), Ok(*tile));
+            assert_eq!(tile.to_string().parse::<Tile>(), Ok(*tile));
+        }
+        assert!("x".parse::<Tile>().is_err());
+    }
+
+    #[test]
+    fn test_explore() {
+        let input_txt = include_str!("../input.txt");
+        let mut sim = Simulation::from_input(input_txt);
+        let tiles = sim.explore().unwrap();
+
+        assert!(tiles.values().any(|&t| t == Tile::Oxygen));
+        assert_eq!(sim.path_to_oxygen().len(), 224);
+    }
+
+    #[test]
+    fn test_explore_step() {
+        let input_txt = include_str!("../input.txt");
+        let mut sim = Simulation::from_input(input_txt);
+        let mut discovered = HashSet::new();
+        let mut oxygen = None;
+
+        while let Some((position, tile)) = sim.explore_step().unwrap() {
+            // Each step discovers a new tile
+            assert!(discovered.insert(position));
+            if tile == Tile::Oxygen {
+                oxygen = Some(position);
+            }
+        }
+
+        assert!(oxygen.is_some());
+        assert_eq!(sim.explore_step(), Ok(None));
+    }
+
+    #[test]
+    fn test_explore_invalid_output() {
+        // Output an unknown tile code for each move
+        let mut sim = Simulation::from_input("3,100,104,7,1105,1,0");
+        assert_eq!(sim.explore_step(), Err("unknown tile code: 7".to_owned()));
+
+        // No output for a move
+        let mut sim = Simulation::from_input("3,100,1105,1,0");
+        assert_eq!(
+            sim.explore_step(),
+            Err("empty interpreter output".to_owned())
+        );
+
+        let mut sim = Simulation::from_input("3,100,1105,1,0");
+        assert!(sim.explore().is_err());
+    }
+
     #[test]
     fn test_results() {
         let input_txt = include_str!("../input.txt");
-        assert_eq!(part1(&input_txt), 224);
-        assert_eq!(part2(&input_txt), 284);
+        assert_eq!(part1(input_txt), 224);
+        assert_eq!(part2(input_txt), 284);
     }
 }